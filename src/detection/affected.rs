@@ -0,0 +1,174 @@
+use serde_json::Value;
+use std::{
+    path::Path,
+    process::{Command, Stdio},
+};
+
+// Names of the projects touched by the changes since `base`. nx knows best (it follows the
+// dependency graph), when it can't tell us we fall back to which project roots git says changed.
+pub fn affected_projects(
+    base_repo_path: &Path,
+    base: &str,
+    projects: &[(String, String)],
+) -> Result<Vec<String>, String> {
+    if let Some(names) = from_nx(base_repo_path, base) {
+        return Ok(names);
+    }
+
+    let files = changed_files(base_repo_path, base)?;
+    Ok(from_changed_files(&files, projects))
+}
+
+fn from_nx(base_repo_path: &Path, base: &str) -> Option<Vec<String>> {
+    let output = Command::new("nx")
+        .args(["show", "projects", "--affected", "--json"])
+        .arg(format!("--base={}", base))
+        .current_dir(base_repo_path)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+
+    let v: Value = serde_json::from_slice(&output.stdout).ok()?;
+    Some(
+        v.as_array()?
+            .iter()
+            .filter_map(|name| name.as_str().map(|name| name.to_string()))
+            .collect(),
+    )
+}
+
+// Committed changes since the branch left `base` plus whatever hasn't been committed yet,
+// relative to the workspace root. --relative keeps diff paths relative to the workspace when it
+// isn't the root of the git repo, ls-files already is.
+fn changed_files(base_repo_path: &Path, base: &str) -> Result<Vec<String>, String> {
+    let committed = git(
+        base_repo_path,
+        &[
+            "diff",
+            "--name-only",
+            "--relative",
+            &format!("{}...HEAD", base),
+        ],
+    )?;
+    let uncommitted = git(
+        base_repo_path,
+        &["diff", "--name-only", "--relative", "HEAD"],
+    )?;
+    let untracked = git(
+        base_repo_path,
+        &["ls-files", "--others", "--exclude-standard"],
+    )?;
+
+    let mut files: Vec<String> = committed
+        .into_iter()
+        .chain(uncommitted)
+        .chain(untracked)
+        .collect();
+    files.sort();
+    files.dedup();
+    Ok(files)
+}
+
+fn git(base_repo_path: &Path, args: &[&str]) -> Result<Vec<String>, String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(base_repo_path)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| format!("Could not run git: {}", e))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("git {} failed: {}", args[0], stderr.trim()));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| line.to_string())
+        .collect())
+}
+
+// Each file belongs to the project with the deepest root containing it
+fn from_changed_files(files: &[String], projects: &[(String, String)]) -> Vec<String> {
+    let mut affected: Vec<String> = Vec::new();
+
+    for file in files {
+        let owner = projects
+            .iter()
+            .filter(|(_, root)| is_within(file, root))
+            .max_by_key(|(_, root)| root.len());
+        if let Some((name, _)) = owner {
+            if !affected.contains(name) {
+                affected.push(name.clone());
+            }
+        }
+    }
+
+    affected
+}
+
+fn is_within(file: &str, root: &str) -> bool {
+    root == "."
+        || file
+            .strip_prefix(root.trim_end_matches('/'))
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn projects() -> Vec<(String, String)> {
+        [
+            ("web", "apps/web"),
+            ("web-e2e", "apps/web-e2e"),
+            ("ui", "libs/ui/"),
+            ("ui-buttons", "libs/ui/buttons"),
+        ]
+        .iter()
+        .map(|(name, root)| (name.to_string(), root.to_string()))
+        .collect()
+    }
+
+    #[test]
+    fn is_within_only_matches_whole_path_segments() {
+        assert!(is_within("apps/web/src/main.ts", "apps/web"));
+        assert!(is_within("libs/ui/index.ts", "libs/ui/"));
+        assert!(!is_within("apps/web-e2e/cypress.config.ts", "apps/web"));
+        assert!(!is_within("apps/web", "apps/web"));
+        assert!(is_within("package.json", "."));
+    }
+
+    #[test]
+    fn files_go_to_the_deepest_project_containing_them() {
+        let files: Vec<String> = [
+            "apps/web-e2e/src/app.cy.ts",
+            "libs/ui/buttons/src/button.tsx",
+            "apps/web/src/main.ts",
+            "apps/web/project.json",
+            "README.md",
+        ]
+        .iter()
+        .map(|file| file.to_string())
+        .collect();
+
+        assert_eq!(
+            from_changed_files(&files, &projects()),
+            vec!["web-e2e", "ui-buttons", "web"]
+        );
+    }
+
+    #[test]
+    fn a_root_project_owns_files_no_other_project_does() {
+        let mut projects = projects();
+        projects.push(("workspace".to_string(), ".".to_string()));
+        let files = vec!["README.md".to_string(), "libs/ui/index.ts".to_string()];
+
+        assert_eq!(
+            from_changed_files(&files, &projects),
+            vec!["workspace", "ui"]
+        );
+    }
+}
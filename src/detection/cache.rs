@@ -0,0 +1,269 @@
+use serde_json::{json, Value};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use crate::utils::workspace_cache_dir;
+
+use super::frameworks::FrameworkRegistry;
+use super::package_json::installed_manifest;
+use super::project::{object, string_list, Project, ProjectType, Task};
+
+// Bump whenever the shape of a cached project, or how it is detected, changes so old caches get
+// thrown away
+const CACHE_VERSION: u64 = 11;
+const CACHE_FILE: &str = "projects.json";
+
+// A project parsed from a config file along with that file's fingerprint
+#[derive(Debug, Clone)]
+pub struct CacheEntry {
+    pub source: String,
+    pub fingerprint: String,
+    pub project: Project,
+}
+
+// The per-file parse results used to skip unchanged files, plus the final project list from the
+// last scan (which may have come from the nx graph) to show at startup
+#[derive(Debug, Default)]
+pub struct ProjectCache {
+    entries: Vec<CacheEntry>,
+    projects: Vec<Project>,
+}
+
+impl ProjectCache {
+    pub fn load(base_repo_path: &Path) -> ProjectCache {
+        let Some(v) = cache_file(base_repo_path)
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|content| serde_json::from_str::<Value>(&content).ok())
+            .filter(|v| v["version"].as_u64() == Some(CACHE_VERSION))
+        else {
+            return ProjectCache::default();
+        };
+
+        let entries = v["entries"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(entry_from_json)
+            .collect();
+        let projects = v["projects"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(project_from_json)
+            .collect();
+
+        ProjectCache { entries, projects }
+    }
+
+    // Caching is best effort, a read-only home directory just means slower startups
+    pub fn save(base_repo_path: &Path, entries: &[CacheEntry], projects: &[Project]) {
+        let Some(path) = cache_file(base_repo_path) else {
+            return;
+        };
+        if let Some(dir) = path.parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+
+        let v = json!({
+            "version": CACHE_VERSION,
+            "entries": entries.iter().map(entry_to_json).collect::<Vec<Value>>(),
+            "projects": projects.iter().map(project_to_json).collect::<Vec<Value>>(),
+        });
+        let _ = fs::write(path, v.to_string());
+    }
+
+    // The cached project for a config file, as long as nothing it was built from has changed
+    pub fn lookup(&self, source: &str, fingerprint: &str) -> Option<&Project> {
+        self.entries
+            .iter()
+            .find(|e| e.source == source)
+            .filter(|e| e.fingerprint == fingerprint)
+            .map(|e| &e.project)
+    }
+
+    pub fn projects(self) -> Vec<Project> {
+        self.projects
+    }
+}
+
+// Modification times of everything a project's parse result depends on: the config file, the
+// package.json merged into it, its directory (for framework identity files), nx.json, the
+// framework definition files, the root package.json and the installed manifest of every
+// framework package (for versions, which an in-place upgrade changes without touching
+// node_modules itself)
+pub fn fingerprint(
+    base_repo_path: &Path,
+    config_path: &Path,
+    frameworks: &FrameworkRegistry,
+) -> String {
+    let mut inputs = vec![
+        config_path.to_path_buf(),
+        config_path.with_file_name("package.json"),
+        base_repo_path.join("nx.json"),
+        base_repo_path.join("package.json"),
+    ];
+    inputs.extend(FrameworkRegistry::config_files(base_repo_path));
+    if let Some(dir) = config_path.parent() {
+        inputs.push(dir.to_path_buf());
+        // A package that isn't installed counts as a missing file, so installing it changes this
+        inputs.extend(frameworks.iter().flat_map(|f| &f.packages).map(|package| {
+            installed_manifest(base_repo_path, dir, package)
+                .unwrap_or_else(|| dir.join("node_modules").join(package))
+        }));
+    }
+
+    inputs
+        .iter()
+        .map(|path| modified_nanos(path).to_string())
+        .collect::<Vec<String>>()
+        .join(":")
+}
+
+fn modified_nanos(path: &Path) -> u128 {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_nanos())
+        .unwrap_or_default()
+}
+
+fn cache_file(base_repo_path: &Path) -> Option<PathBuf> {
+    workspace_cache_dir(base_repo_path).map(|dir| dir.join(CACHE_FILE))
+}
+
+fn entry_to_json(entry: &CacheEntry) -> Value {
+    json!({
+        "source": entry.source,
+        "fingerprint": entry.fingerprint,
+        "project": project_to_json(&entry.project),
+    })
+}
+
+fn entry_from_json(v: &Value) -> Option<CacheEntry> {
+    Some(CacheEntry {
+        source: v["source"].as_str()?.to_string(),
+        fingerprint: v["fingerprint"].as_str()?.to_string(),
+        project: project_from_json(&v["project"])?,
+    })
+}
+
+fn project_to_json(project: &Project) -> Value {
+    json!({
+        "name": project.name,
+        "projectType": match project.project_type {
+            ProjectType::Library => "library",
+            ProjectType::Application => "application",
+        },
+        "root": project.root,
+        "sourceRoot": project.source_root,
+        "tags": project.tags,
+        "implicitDependencies": project.implicit_dependencies,
+        "namedInputs": project.named_inputs,
+        "frameworks": project.frameworks,
+        "tasks": project.tasks.iter().map(|t| json!({
+            "command": t.command,
+            "subcommands": t.subcommands,
+            "executor": t.executor,
+            "options": t.options,
+            "configurations": t.configurations,
+            "defaultConfiguration": t.default_configuration,
+            "dependsOn": t.depends_on,
+            "inputs": t.inputs,
+            "outputs": t.outputs,
+            "cache": t.cache,
+        })).collect::<Vec<Value>>(),
+    })
+}
+
+fn project_from_json(v: &Value) -> Option<Project> {
+    let tasks = v["tasks"]
+        .as_array()?
+        .iter()
+        .map(|t| {
+            Some(Task {
+                command: t["command"].as_str()?.to_string(),
+                subcommands: t["subcommands"]
+                    .as_array()?
+                    .iter()
+                    .filter_map(|s| s.as_str().map(|s| s.to_string()))
+                    .collect(),
+                executor: t["executor"].as_str().map(|e| e.to_string()),
+                options: t["options"].as_object().cloned().unwrap_or_default(),
+                configurations: t["configurations"].as_object().cloned().unwrap_or_default(),
+                default_configuration: t["defaultConfiguration"].as_str().map(|c| c.to_string()),
+                depends_on: t["dependsOn"].as_array().cloned().unwrap_or_default(),
+                inputs: t["inputs"].as_array().cloned().unwrap_or_default(),
+                outputs: t["outputs"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|o| o.as_str().map(|o| o.to_string()))
+                    .collect(),
+                cache: t["cache"].as_bool(),
+            })
+        })
+        .collect::<Option<Vec<Task>>>()?;
+
+    let frameworks = serde_json::from_value(v["frameworks"].clone()).ok()?;
+
+    Some(Project {
+        name: v["name"].as_str()?.to_string(),
+        project_type: match v["projectType"].as_str()? {
+            "library" => ProjectType::Library,
+            _other => ProjectType::Application,
+        },
+        root: v["root"].as_str()?.to_string(),
+        source_root: v["sourceRoot"].as_str().map(|s| s.to_string()),
+        tags: string_list(v, "tags"),
+        implicit_dependencies: string_list(v, "implicitDependencies"),
+        named_inputs: object(v, "namedInputs"),
+        tasks,
+        frameworks,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_env;
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn upgrading_a_framework_package_in_place_changes_the_fingerprint() {
+        // The fingerprint takes in the user's framework files from XDG_CONFIG_HOME
+        let _env = test_env::lock();
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let config = root.join("apps/site/project.json");
+        fs::create_dir_all(config.parent().unwrap()).unwrap();
+        fs::write(&config, "{}").unwrap();
+        let manifest = root.join("node_modules/next/package.json");
+        fs::create_dir_all(manifest.parent().unwrap()).unwrap();
+        fs::write(&manifest, r#"{"version": "14.1.0"}"#).unwrap();
+
+        let frameworks = FrameworkRegistry::builtin();
+        let before = fingerprint(root, &config, &frameworks);
+
+        let node_modules_modified = fs::metadata(root.join("node_modules"))
+            .and_then(|m| m.modified())
+            .unwrap();
+        fs::write(&manifest, r#"{"version": "14.2.0"}"#).unwrap();
+        fs::File::options()
+            .write(true)
+            .open(&manifest)
+            .and_then(|f| f.set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(1)))
+            .unwrap();
+
+        assert_eq!(
+            fs::metadata(root.join("node_modules"))
+                .and_then(|m| m.modified())
+                .unwrap(),
+            node_modules_modified
+        );
+        assert_ne!(fingerprint(root, &config, &frameworks), before);
+    }
+}
@@ -0,0 +1,228 @@
+use serde_json::Value;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+// How an option can be edited, from the `type` and `enum` its schema gives it
+#[derive(Debug, Clone, PartialEq)]
+pub enum OptionKind {
+    Boolean,
+    Enum(Vec<String>),
+    String,
+    Number,
+}
+
+// One property from an executor's schema.json
+#[derive(Debug, Clone)]
+pub struct ExecutorOption {
+    pub name: String,
+    pub kind: OptionKind,
+    pub description: Option<String>,
+    pub default: Option<Value>,
+}
+
+// The options an executor like `@nx/vite:build` takes, read from the schema.json its package
+// ships. The package is looked up the way node would from the project, so a project pinned to
+// its own version of a plugin gets that version's options. Arrays and objects can't be given as
+// a single `--option=value` so they're left out, as are options the schema hides.
+pub fn executor_options(
+    base_repo_path: &Path,
+    project_path: &Path,
+    executor: &str,
+) -> Result<Vec<ExecutorOption>, String> {
+    let (package, name) = executor
+        .split_once(':')
+        .ok_or_else(|| format!("{} isn't a package:executor pair", executor))?;
+
+    let package_dir = package_dir(base_repo_path, project_path, package)
+        .ok_or_else(|| format!("{} isn't installed", package))?;
+    let schema_path = schema_path(&package_dir, name)
+        .ok_or_else(|| format!("{} doesn't describe {}", package, name))?;
+    let schema = read_json(&schema_path)
+        .ok_or_else(|| format!("Could not read {}", schema_path.display()))?;
+
+    let Some(properties) = schema.get("properties").and_then(|p| p.as_object()) else {
+        return Ok(vec![]);
+    };
+
+    Ok(properties
+        .iter()
+        .filter(|(_, property)| property.get("hidden").and_then(|h| h.as_bool()) != Some(true))
+        .filter_map(|(name, property)| {
+            Some(ExecutorOption {
+                name: name.clone(),
+                kind: option_kind(property)?,
+                description: property
+                    .get("description")
+                    .and_then(|d| d.as_str())
+                    .map(|d| d.to_string()),
+                default: property.get("default").cloned(),
+            })
+        })
+        .collect())
+}
+
+// Workspace-local plugins are referenced by path, everything else lives in node_modules
+fn package_dir(base_repo_path: &Path, project_path: &Path, package: &str) -> Option<PathBuf> {
+    if package.starts_with('.') {
+        let dir = base_repo_path.join(package);
+        return dir.join("package.json").is_file().then_some(dir);
+    }
+
+    project_path
+        .ancestors()
+        .take_while(|dir| dir.starts_with(base_repo_path))
+        .map(|dir| dir.join("node_modules").join(package))
+        .find(|dir| dir.join("package.json").is_file())
+}
+
+// package.json points at executors.json (or builders.json for Angular's older packages), which
+// maps each executor name to its schema, both paths relative to the file they're in
+fn schema_path(package_dir: &Path, name: &str) -> Option<PathBuf> {
+    let manifest = read_json(&package_dir.join("package.json"))?;
+    let collection = ["executors", "builders"]
+        .iter()
+        .find_map(|key| manifest.get(key).and_then(|c| c.as_str()))
+        .unwrap_or("executors.json");
+    let collection_path = package_dir.join(collection);
+    let collection = read_json(&collection_path)?;
+
+    let schema = ["executors", "builders"]
+        .iter()
+        .find_map(|key| collection.get(key)?.get(name)?.get("schema")?.as_str())?;
+    Some(collection_path.parent()?.join(schema))
+}
+
+fn option_kind(property: &Value) -> Option<OptionKind> {
+    if let Some(values) = property.get("enum").and_then(|e| e.as_array()) {
+        let values: Vec<String> = values
+            .iter()
+            .filter_map(|v| v.as_str().map(|v| v.to_string()))
+            .collect();
+        if !values.is_empty() {
+            return Some(OptionKind::Enum(values));
+        }
+    }
+
+    match property.get("type").and_then(|t| t.as_str()) {
+        Some("boolean") => Some(OptionKind::Boolean),
+        Some("string") => Some(OptionKind::String),
+        Some("number" | "integer") => Some(OptionKind::Number),
+        _ => None,
+    }
+}
+
+fn read_json(path: &Path) -> Option<Value> {
+    serde_json::from_str(&fs::read_to_string(path).ok()?).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn write(dir: &Path, path: &str, content: Value) {
+        let path = dir.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content.to_string()).unwrap();
+    }
+
+    fn names(options: &[ExecutorOption]) -> Vec<&str> {
+        options.iter().map(|o| o.name.as_str()).collect()
+    }
+
+    #[test]
+    fn options_come_from_the_schema_the_package_points_at() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path();
+        write(
+            base,
+            "node_modules/@x/y/package.json",
+            json!({"name": "@x/y", "executors": "./executors.json"}),
+        );
+        write(
+            base,
+            "node_modules/@x/y/executors.json",
+            json!({"executors": {"build": {"schema": "./src/build/schema.json"}}}),
+        );
+        write(
+            base,
+            "node_modules/@x/y/src/build/schema.json",
+            json!({"properties": {
+                "mode": {"type": "string", "enum": ["development", "production"]},
+                "watch": {"type": "boolean", "default": false, "description": "Rebuild on change"},
+                "port": {"type": "integer"},
+                "outputPath": {"type": "string"},
+                "assets": {"type": "array", "items": {"type": "string"}},
+                "define": {"type": "object"},
+                "internal": {"type": "string", "hidden": true},
+                "anything": {}
+            }}),
+        );
+
+        let options = executor_options(base, &base.join("apps/web"), "@x/y:build").unwrap();
+        assert_eq!(names(&options), vec!["mode", "outputPath", "port", "watch"]);
+
+        let kinds: Vec<&OptionKind> = options.iter().map(|o| &o.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                &OptionKind::Enum(vec!["development".into(), "production".into()]),
+                &OptionKind::String,
+                &OptionKind::Number,
+                &OptionKind::Boolean,
+            ]
+        );
+        assert_eq!(options[3].default, Some(json!(false)));
+        assert_eq!(options[3].description.as_deref(), Some("Rebuild on change"));
+    }
+
+    #[test]
+    fn a_project_pinned_to_its_own_version_gets_its_schema() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path();
+        for (root, option) in [("", "old"), ("apps/web/", "new")] {
+            let package = format!("{}node_modules/@x/y", root);
+            // No collection in package.json means executors.json, builders.json uses builders
+            write(base, &format!("{}/package.json", package), json!({}));
+            write(
+                base,
+                &format!("{}/executors.json", package),
+                json!({"builders": {"build": {"schema": "schema.json"}}}),
+            );
+            write(
+                base,
+                &format!("{}/schema.json", package),
+                json!({"properties": {option: {"type": "boolean"}}}),
+            );
+        }
+
+        let web = executor_options(base, &base.join("apps/web"), "@x/y:build").unwrap();
+        assert_eq!(names(&web), vec!["new"]);
+        let api = executor_options(base, &base.join("apps/api"), "@x/y:build").unwrap();
+        assert_eq!(names(&api), vec!["old"]);
+    }
+
+    #[test]
+    fn missing_packages_and_executors_are_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path();
+        write(base, "node_modules/@x/y/package.json", json!({}));
+        write(
+            base,
+            "node_modules/@x/y/executors.json",
+            json!({"executors": {}}),
+        );
+
+        assert!(executor_options(base, base, "@x/y").is_err());
+        assert_eq!(
+            executor_options(base, base, "@x/z:build").unwrap_err(),
+            "@x/z isn't installed"
+        );
+        assert_eq!(
+            executor_options(base, base, "@x/y:build").unwrap_err(),
+            "@x/y doesn't describe build"
+        );
+    }
+}
@@ -1,67 +1,143 @@
-use super::project::{DeepDetectionMatcher, Framework};
-
-pub const KNOWN_FRAMEWORKS: &[Framework] = &[
-    Framework {
-        name: "nextjs",
-        identity_files: &[
-            "next.config.js",
-            "next.config.ts",
-            "next.config.mjs",
-            "next.config.cjs",
-        ],
-        proj_identity_keywords: &[],
-        deep_detection_matchers: &[],
-        commands: &["dev", "build", "start"],
-    },
-    Framework {
-        name: "nuxt",
-        identity_files: &["nuxt.config.js", "nuxt.config.ts"],
-        proj_identity_keywords: &[],
-        deep_detection_matchers: &[],
-        commands: &["dev", "build", "start"],
-    },
-    Framework {
-        name: "angular",
-        identity_files: &["angular.json"],
-        proj_identity_keywords: &["@angular"],
-        deep_detection_matchers: &[],
-        commands: &["serve", "build"],
-    },
-    Framework {
-        name: "nestjs",
-        identity_files: &[],
-        proj_identity_keywords: &[],
-        deep_detection_matchers: &[DeepDetectionMatcher {
-            path: "src/main.ts",
-            keyword: "@nestjs/common",
-        }],
-        commands: &["start", "build"],
-    },
-    Framework {
-        name: "cypress",
-        identity_files: &[
-            "cypress.config.ts",
-            &"cypress.json",
-            &"cypress.config.js",
-            &"cypress.config.mjs",
-            &"cypress.config.cjs",
-        ],
-        proj_identity_keywords: &[],
-        deep_detection_matchers: &[],
-        commands: &["open-cypress", "e2e"],
-    },
-    Framework {
-        name: "Vite",
-        identity_files: &[
-            "vite.config.ts",
-            "vite.config.js",
-            "vite.config.mjs",
-            "vite.config.cjs",
-            "vite.config.mts",
-            "vite.config.cts",
-        ],
-        proj_identity_keywords: &[],
-        deep_detection_matchers: &[],
-        commands: &["serve", "build"],
-    },
-];
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::utils::user_config_dir;
+
+use super::project::{Diagnostic, Framework, ProjectError};
+
+// The built-in definitions, in the same format as the user and workspace files
+const BUILTIN_FRAMEWORKS: &str = include_str!("frameworks.json");
+
+// Framework definition files, later ones override earlier ones by name. New names go after the
+// built-ins unless they set a priority.
+const WORKSPACE_FRAMEWORKS_FILE: &str = ".nxplorer/frameworks.json";
+const USER_FRAMEWORKS_FILE: &str = "frameworks.json";
+
+#[derive(Debug, Clone, Default)]
+pub struct FrameworkRegistry {
+    frameworks: Vec<Framework>,
+}
+
+impl FrameworkRegistry {
+    // The built-in list with the user's and then the workspace's definitions merged on top.
+    // A broken definition file is reported and skipped rather than losing the built-ins.
+    pub fn load(base_repo_path: &Path, diagnostics: &mut Vec<Diagnostic>) -> FrameworkRegistry {
+        let mut registry = FrameworkRegistry::builtin();
+
+        for path in Self::config_files(base_repo_path) {
+            if !path.exists() {
+                continue;
+            }
+            match Self::read_file(&path) {
+                Ok(frameworks) => {
+                    registry.merge(Self::valid_frameworks(&path, frameworks, diagnostics))
+                }
+                Err(e) => diagnostics.push(Diagnostic::error(&path, e)),
+            }
+        }
+
+        registry
+    }
+
+    pub fn builtin() -> FrameworkRegistry {
+        FrameworkRegistry {
+            frameworks: serde_json::from_str(BUILTIN_FRAMEWORKS)
+                .expect("built-in framework definitions are valid"),
+        }
+    }
+
+    // Every file that can contribute framework definitions, so changes to them can invalidate caches
+    pub fn config_files(base_repo_path: &Path) -> Vec<PathBuf> {
+        user_config_dir()
+            .map(|dir| dir.join(USER_FRAMEWORKS_FILE))
+            .into_iter()
+            .chain([base_repo_path.join(WORKSPACE_FRAMEWORKS_FILE)])
+            .collect()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Framework> {
+        self.frameworks.iter()
+    }
+
+    fn read_file(path: &Path) -> Result<Vec<Framework>, ProjectError> {
+        let content = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    // A definition with a matcher that can't be evaluated is reported and left out, which keeps
+    // any built-in definition of the same name in place
+    fn valid_frameworks(
+        path: &Path,
+        frameworks: Vec<Framework>,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) -> Vec<Framework> {
+        frameworks
+            .into_iter()
+            .filter(|framework| {
+                match framework
+                    .deep_detection_matchers
+                    .iter()
+                    .try_for_each(|matcher| matcher.validate())
+                {
+                    Ok(()) => true,
+                    Err(e) => {
+                        let error =
+                            ProjectError::InvalidMatcher(format!("{}: {}", framework.name, e));
+                        diagnostics.push(Diagnostic::error(path, error));
+                        false
+                    }
+                }
+            })
+            .collect()
+    }
+
+    // A definition with the name of an existing one replaces it, anything else is added after
+    // the others with the same priority
+    fn merge(&mut self, frameworks: Vec<Framework>) {
+        for framework in frameworks {
+            match self
+                .frameworks
+                .iter_mut()
+                .find(|f| f.name.eq_ignore_ascii_case(&framework.name))
+            {
+                Some(existing) => *existing = framework,
+                None => self.frameworks.push(framework),
+            }
+        }
+        // Stable, so definitions with the same priority keep their file order
+        self.frameworks
+            .sort_by_key(|framework| std::cmp::Reverse(framework.priority));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry_with(definitions: &str) -> Vec<String> {
+        let mut registry = FrameworkRegistry::builtin();
+        registry.merge(serde_json::from_str(definitions).unwrap());
+        registry.iter().map(|f| f.name.clone()).collect()
+    }
+
+    #[test]
+    fn new_definitions_go_after_the_builtins() {
+        let names = registry_with(r#"[{"name": "custom"}]"#);
+        assert_eq!(names.last().map(String::as_str), Some("custom"));
+    }
+
+    #[test]
+    fn a_priority_puts_a_definition_first() {
+        let names = registry_with(r#"[{"name": "custom", "priority": 1}]"#);
+        assert_eq!(names[0], "custom");
+    }
+
+    #[test]
+    fn overriding_a_builtin_keeps_its_place() {
+        let before = registry_with("[]");
+        let after = registry_with(r#"[{"name": "Vite", "commands": ["preview"]}]"#);
+        assert_eq!(before, after);
+    }
+}
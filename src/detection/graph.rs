@@ -0,0 +1,204 @@
+use serde_json::Value;
+use std::{
+    fs,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+use super::frameworks::FrameworkRegistry;
+use super::project::{object, string_list, Diagnostic, Project, ProjectType, Task};
+
+// Where nx keeps the last graph it computed, used when we can't get a fresh one out of nx itself
+const CACHED_GRAPH_PATH: &str = ".nx/workspace-data/project-graph.json";
+
+// Loads projects from the nx project graph, which includes everything nx plugins infer.
+// Returns None when no graph could be produced, leaving the caller with what walking the tree
+// found.
+pub fn load(
+    base_repo_path: &Path,
+    frameworks: &FrameworkRegistry,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Option<Vec<Project>> {
+    let graph = generate_graph(base_repo_path).or_else(|| read_cached_graph(base_repo_path))?;
+    let projects = parse_graph(base_repo_path, &graph, frameworks, diagnostics);
+
+    if projects.is_empty() {
+        return None;
+    }
+    Some(projects)
+}
+
+fn generate_graph(base_repo_path: &Path) -> Option<Value> {
+    // Created exclusively with a random name, so nobody else can have put a file or a link there
+    // first. It's removed again when dropped.
+    let graph_file = tempfile::Builder::new()
+        .prefix("nxplorer-graph-")
+        .suffix(".json")
+        .tempfile()
+        .ok()?;
+
+    // nx prints progress to stdout/stderr, which would scribble all over the alternate screen
+    let status = Command::new("nx")
+        .arg("graph")
+        .arg(format!("--file={}", graph_file.path().display()))
+        .current_dir(base_repo_path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .ok()?;
+
+    if !status.success() {
+        return None;
+    }
+
+    // `nx graph --file` wraps the graph in a "graph" key
+    let content = fs::read_to_string(graph_file.path()).ok()?;
+    let v: Value = serde_json::from_str(&content).ok()?;
+    v.get("graph").cloned()
+}
+
+fn read_cached_graph(base_repo_path: &Path) -> Option<Value> {
+    let content = fs::read_to_string(base_repo_path.join(CACHED_GRAPH_PATH)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn parse_graph(
+    base_repo_path: &Path,
+    graph: &Value,
+    frameworks: &FrameworkRegistry,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Vec<Project> {
+    let Some(nodes) = graph.get("nodes").and_then(|n| n.as_object()) else {
+        return vec![];
+    };
+
+    nodes
+        .iter()
+        .map(|(key, node)| {
+            let data = &node["data"];
+            let name = node["name"].as_str().unwrap_or(key).to_string();
+
+            // data.projectType is what the project declared, the node type is what nx decided
+            let project_type = match data["projectType"].as_str().or(node["type"].as_str()) {
+                Some("library") | Some("lib") => ProjectType::Library,
+                _other => ProjectType::Application,
+            };
+
+            let root = data["root"].as_str().unwrap_or(".");
+            let project = Project {
+                name,
+                project_type,
+                root: root.to_string(),
+                source_root: data["sourceRoot"].as_str().map(|s| s.to_string()),
+                tags: string_list(data, "tags"),
+                implicit_dependencies: string_list(data, "implicitDependencies"),
+                named_inputs: object(data, "namedInputs"),
+                tasks: Task::from_targets(&data["targets"]),
+                frameworks: vec![],
+            };
+
+            project.with_frameworks(
+                base_repo_path,
+                &base_repo_path.join(root),
+                frameworks,
+                diagnostics,
+            )
+        })
+        .collect()
+}
+
+// The stub nx is a shell script
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::detection::project::ScanEvent;
+    use crate::utils::test_env::{self, EnvGuard};
+    use std::{env, os::unix::fs::PermissionsExt, sync::mpsc};
+
+    const GRAPH: &str = r#"{"graph": {"nodes": {
+        "web": {"name": "web", "type": "app", "data": {
+            "root": "apps/web", "tags": ["scope:web"],
+            "targets": {"build": {"executor": "@nx/vite:build", "configurations": {"production": {}}}}
+        }},
+        "ui": {"name": "ui", "type": "lib", "data": {
+            "root": "libs/ui", "projectType": "library",
+            "targets": {"test": {"executor": "@nx/jest:jest"}}
+        }}
+    }}}"#;
+
+    // A workspace with one project.json, and an `nx` on PATH that runs `script`. The environment
+    // stays that way until the guard is dropped.
+    fn workspace(script: &str) -> (tempfile::TempDir, EnvGuard) {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::write(root.join("nx.json"), "{}").unwrap();
+        fs::create_dir_all(root.join("apps/api")).unwrap();
+        fs::write(
+            root.join("apps/api/project.json"),
+            r#"{"name": "api", "projectType": "application", "targets": {"serve": {}}}"#,
+        )
+        .unwrap();
+        fs::write(root.join("graph.json"), GRAPH).unwrap();
+
+        let bin = root.join("bin");
+        fs::create_dir(&bin).unwrap();
+        let nx = bin.join("nx");
+        fs::write(&nx, format!("#!/bin/sh\n{}\n", script)).unwrap();
+        fs::set_permissions(&nx, fs::Permissions::from_mode(0o755)).unwrap();
+
+        let mut env = test_env::lock();
+        let path = format!("{}:{}", bin.display(), env::var("PATH").unwrap_or_default());
+        env.set("PATH", path);
+        env.set("XDG_CACHE_HOME", root.join("cache"));
+        env.set("XDG_CONFIG_HOME", root.join("config"));
+        (dir, env)
+    }
+
+    #[test]
+    fn loads_projects_from_the_graph_nx_writes() {
+        let (dir, _env) = workspace(
+            r#"for arg in "$@"; do case "$arg" in --file=*) cp graph.json "${arg#--file=}";; esac; done"#,
+        );
+
+        let mut diagnostics = Vec::new();
+        let frameworks = FrameworkRegistry::load(dir.path(), &mut diagnostics);
+        let mut projects = load(dir.path(), &frameworks, &mut diagnostics).unwrap();
+        projects.sort_by(|a, b| a.name.cmp(&b.name));
+
+        assert_eq!(projects.len(), 2);
+        let (ui, web) = (&projects[0], &projects[1]);
+        assert_eq!(ui.name, "ui");
+        assert!(matches!(ui.project_type, ProjectType::Library));
+        assert_eq!(ui.root, "libs/ui");
+        assert_eq!(web.name, "web");
+        assert!(matches!(web.project_type, ProjectType::Application));
+        assert_eq!(web.tags, vec!["scope:web"]);
+        assert_eq!(web.tasks[0].command, "build");
+        assert_eq!(web.tasks[0].subcommands, vec!["production"]);
+        assert_eq!(web.tasks[0].executor.as_deref(), Some("@nx/vite:build"));
+    }
+
+    #[test]
+    fn falls_back_to_the_file_walk_when_nx_fails() {
+        let (dir, _env) = workspace("exit 1");
+
+        let mut diagnostics = Vec::new();
+        let frameworks = FrameworkRegistry::load(dir.path(), &mut diagnostics);
+        assert!(load(dir.path(), &frameworks, &mut diagnostics).is_none());
+
+        let (tx, rx) = mpsc::channel();
+        Project::detect(dir.path(), &tx);
+        drop(tx);
+
+        let mut names = Vec::new();
+        for event in rx {
+            match event {
+                ScanEvent::Scanned(Some(project)) => names.push(project.name),
+                ScanEvent::Replaced(_) => panic!("no graph should have been loaded"),
+                _ => (),
+            }
+        }
+        assert_eq!(names, vec!["api"]);
+    }
+}
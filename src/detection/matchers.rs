@@ -0,0 +1,252 @@
+use glob::{MatchOptions, Pattern};
+use ignore::WalkBuilder;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::File,
+    io::Read,
+    path::{Path, PathBuf},
+    sync::{Arc, OnceLock},
+};
+
+use super::project::{Diagnostic, ProjectError};
+
+// Entry points and configs are small, anything bigger is probably a bundle we don't want to read
+const DEFAULT_MAX_BYTES: u64 = 256 * 1024;
+
+// A rule that looks inside a project's files. Written in JSON as either a file matcher
+// (`{ "path": "src/main*.ts", "regex": "..." }`) or a combinator (`{ "allOf": [...] }`,
+// `{ "anyOf": [...] }`) over other matchers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged, rename_all_fields = "camelCase")]
+pub enum DeepDetectionMatcher {
+    AllOf { all_of: Vec<DeepDetectionMatcher> },
+    AnyOf { any_of: Vec<DeepDetectionMatcher> },
+    File(FileMatcher),
+}
+
+// Matches when any file under the project matching `path` contains `keyword` and matches `regex`.
+// With neither set, the file existing is enough.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileMatcher {
+    // Glob, relative to the project root
+    pub path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub keyword: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub regex: Option<String>,
+    // Only this much of each file is searched
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_bytes: Option<u64>,
+    // Compiled on first use. Behind an Arc so every project's clone of the definition shares it.
+    #[serde(skip)]
+    compiled: Arc<OnceLock<Option<Regex>>>,
+}
+
+impl DeepDetectionMatcher {
+    // Bad globs and regexes are caught when the definitions are loaded, so matching can't fail
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            Self::AllOf { all_of: matchers } | Self::AnyOf { any_of: matchers } => {
+                matchers.iter().try_for_each(|m| m.validate())
+            }
+            Self::File(matcher) => matcher.validate(),
+        }
+    }
+
+    pub fn matches(&self, project_path: &Path, diagnostics: &mut Vec<Diagnostic>) -> bool {
+        match self {
+            Self::AllOf { all_of } => all_of.iter().all(|m| m.matches(project_path, diagnostics)),
+            Self::AnyOf { any_of } => any_of.iter().any(|m| m.matches(project_path, diagnostics)),
+            Self::File(matcher) => matcher.matches(project_path, diagnostics),
+        }
+    }
+}
+
+impl FileMatcher {
+    fn validate(&self) -> Result<(), String> {
+        Pattern::new(&self.path).map_err(|e| format!("path {:?}: {}", self.path, e))?;
+
+        if let Some(regex) = &self.regex {
+            Regex::new(regex).map_err(|e| format!("regex {:?}: {}", regex, e))?;
+        }
+        Ok(())
+    }
+
+    fn matches(&self, project_path: &Path, diagnostics: &mut Vec<Diagnostic>) -> bool {
+        let regex = match &self.regex {
+            Some(regex) => match self.compiled.get_or_init(|| Regex::new(regex).ok()) {
+                Some(compiled) => Some(compiled),
+                None => return false,
+            },
+            None => None,
+        };
+
+        self.files(project_path).iter().any(|path| {
+            if self.keyword.is_none() && regex.is_none() {
+                return true;
+            }
+
+            match read_prefix(path, self.max_bytes.unwrap_or(DEFAULT_MAX_BYTES)) {
+                Ok(content) => {
+                    self.keyword
+                        .as_ref()
+                        .is_none_or(|keyword| content.contains(keyword.as_str()))
+                        && regex.is_none_or(|regex| regex.is_match(&content))
+                }
+                Err(e) => {
+                    diagnostics.push(Diagnostic::error(path, e));
+                    false
+                }
+            }
+        })
+    }
+
+    // Files under the project matching the glob, in a stable order. Only the part of the tree
+    // the glob can reach is walked, and never node_modules, which `**` would otherwise wander into.
+    fn files(&self, project_path: &Path) -> Vec<PathBuf> {
+        let Ok(pattern) = Pattern::new(&self.path) else {
+            return vec![];
+        };
+        let options = MatchOptions {
+            require_literal_separator: true,
+            ..MatchOptions::new()
+        };
+
+        // Everything up to the first component with a wildcard in it is a plain directory
+        let components: Vec<&str> = self.path.split('/').collect();
+        let literal = components
+            .iter()
+            .take_while(|c| !c.contains(['*', '?', '[']))
+            .count();
+        if literal == components.len() {
+            let path = project_path.join(&self.path);
+            return if path.is_file() { vec![path] } else { vec![] };
+        }
+
+        let start = project_path.join(components[..literal].join("/"));
+        let depth = match self.path.contains("**") {
+            true => None,
+            false => Some(components.len() - literal),
+        };
+        let mut files: Vec<PathBuf> = WalkBuilder::new(&start)
+            .standard_filters(false)
+            .max_depth(depth)
+            .filter_entry(|entry| entry.file_name() != "node_modules")
+            .build()
+            .flatten()
+            .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+            .map(|entry| entry.into_path())
+            .filter(|path| {
+                path.strip_prefix(project_path)
+                    .is_ok_and(|relative| pattern.matches_path_with(relative, options))
+            })
+            .collect();
+        files.sort();
+        files
+    }
+}
+
+// The first `max_bytes` of a file as text. A character cut in half by the limit is dropped.
+fn read_prefix(path: &Path, max_bytes: u64) -> Result<String, ProjectError> {
+    let unreadable = |e| ProjectError::UnreadableFile(path.to_path_buf(), e);
+
+    let mut bytes = Vec::new();
+    File::open(path)
+        .and_then(|file| file.take(max_bytes).read_to_end(&mut bytes))
+        .map_err(unreadable)?;
+
+    match String::from_utf8(bytes) {
+        Ok(content) => Ok(content),
+        Err(e) if e.utf8_error().error_len().is_none() => {
+            let valid_up_to = e.utf8_error().valid_up_to();
+            let mut bytes = e.into_bytes();
+            bytes.truncate(valid_up_to);
+            Ok(String::from_utf8(bytes).unwrap_or_default())
+        }
+        Err(_) => Err(ProjectError::InvalidUtf8(path.to_path_buf())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn matcher(json: &str) -> DeepDetectionMatcher {
+        let matcher: DeepDetectionMatcher = serde_json::from_str(json).unwrap();
+        matcher.validate().unwrap();
+        matcher
+    }
+
+    fn project(files: &[(&str, &str)]) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        for (path, content) in files {
+            let path = dir.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn double_star_globs_skip_node_modules() {
+        let matcher = matcher(r#"{"path": "**/*.ts", "keyword": "@Module"}"#);
+        let mut diagnostics = Vec::new();
+
+        let dir = project(&[("node_modules/lib/index.ts", "@Module")]);
+        assert!(!matcher.matches(dir.path(), &mut diagnostics));
+
+        let dir = project(&[("src/app/app.module.ts", "@Module")]);
+        assert!(matcher.matches(dir.path(), &mut diagnostics));
+    }
+
+    #[test]
+    fn single_star_stays_in_its_directory() {
+        let matcher = matcher(r#"{"path": "src/main*.ts"}"#);
+        let mut diagnostics = Vec::new();
+
+        let dir = project(&[("src/nested/main.ts", "")]);
+        assert!(!matcher.matches(dir.path(), &mut diagnostics));
+
+        let dir = project(&[("src/main.server.ts", "")]);
+        assert!(matcher.matches(dir.path(), &mut diagnostics));
+    }
+
+    #[test]
+    fn combinators_and_regexes() {
+        let matcher = matcher(
+            r#"{"allOf": [
+                {"path": "package.json", "regex": "\"@nestjs/core\"\\s*:"},
+                {"anyOf": [{"path": "src/main.ts"}, {"path": "src/main.js"}]}
+            ]}"#,
+        );
+        let mut diagnostics = Vec::new();
+
+        let dir = project(&[
+            (
+                "package.json",
+                r#"{"dependencies": {"@nestjs/core": "10"}}"#,
+            ),
+            ("src/main.js", ""),
+        ]);
+        assert!(matcher.matches(dir.path(), &mut diagnostics));
+
+        let dir = project(&[(
+            "package.json",
+            r#"{"dependencies": {"@nestjs/core": "10"}}"#,
+        )]);
+        assert!(!matcher.matches(dir.path(), &mut diagnostics));
+    }
+
+    #[test]
+    fn clones_share_the_compiled_regex() {
+        let DeepDetectionMatcher::File(original) = matcher(r#"{"path": "a", "regex": "b"}"#) else {
+            panic!("expected a file matcher");
+        };
+        let clone = original.clone();
+        clone.matches(Path::new("/nonexistent"), &mut Vec::new());
+        assert!(original.compiled.get().is_some());
+    }
+}
@@ -1,4 +1,12 @@
+pub mod affected;
+pub mod cache;
+pub mod executor_schema;
 pub mod frameworks;
+pub mod graph;
+pub mod matchers;
+pub mod nx_json;
+pub mod package_json;
 pub mod project;
+pub mod targets;
 
 pub use project::Project;
@@ -0,0 +1,138 @@
+use glob::Pattern;
+use serde_json::{Map, Value};
+use std::{fs, path::Path};
+
+use super::project::ProjectError;
+use super::targets::merge_target;
+
+// The parts of the workspace nx.json that shape what each project ends up with
+#[derive(Debug, Default)]
+pub struct NxJson {
+    pub target_defaults: Map<String, Value>,
+    pub cache_directory: Option<String>,
+    // What affected commands compare against when not given a base
+    pub default_base: Option<String>,
+}
+
+impl NxJson {
+    pub fn read(base_repo_path: &Path) -> Result<NxJson, ProjectError> {
+        let content = fs::read_to_string(base_repo_path.join("nx.json"))?;
+        let v: Value = serde_json::from_str(&content)?;
+
+        let target_defaults = v
+            .get("targetDefaults")
+            .and_then(|t| t.as_object())
+            .cloned()
+            .unwrap_or_default();
+
+        let cache_directory = v
+            .get("cacheDirectory")
+            .and_then(|c| c.as_str())
+            .map(|c| c.to_string());
+
+        // Older workspaces keep it under "affected"
+        let default_base = v
+            .get("defaultBase")
+            .or_else(|| v.get("affected").and_then(|a| a.get("defaultBase")))
+            .and_then(|b| b.as_str())
+            .map(|b| b.to_string());
+
+        Ok(NxJson {
+            target_defaults,
+            cache_directory,
+            default_base,
+        })
+    }
+
+    // Globs for the walk to skip on top of .gitignore/.nxignore, anchored to the workspace root
+    pub fn ignore_globs(&self) -> Vec<String> {
+        self.cache_directory
+            .iter()
+            .map(|dir| format!("/{}", dir.trim_start_matches("./").trim_end_matches('/')))
+            .collect()
+    }
+
+    // Same lookup order as nx: executor first, then the exact target name, then glob keys
+    fn target_defaults_for(&self, target_name: &str, executor: Option<&str>) -> Option<&Value> {
+        if let Some(defaults) = executor.and_then(|e| self.target_defaults.get(e)) {
+            return Some(defaults);
+        }
+
+        if let Some(defaults) = self.target_defaults.get(target_name) {
+            return Some(defaults);
+        }
+
+        self.target_defaults.iter().find_map(|(key, defaults)| {
+            Pattern::new(key)
+                .ok()
+                .filter(|pattern| pattern.matches(target_name))
+                .map(|_| defaults)
+        })
+    }
+
+    // Layers each target on top of its targetDefaults, so the target itself always wins
+    pub fn apply_target_defaults(&self, targets: &mut Map<String, Value>) {
+        if self.target_defaults.is_empty() {
+            return;
+        }
+
+        for (name, target) in targets.iter_mut() {
+            let executor = target.get("executor").and_then(|e| e.as_str());
+            if let Some(defaults) = self.target_defaults_for(name, executor) {
+                *target = merge_target(defaults, target);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn nx_json(target_defaults: Value) -> NxJson {
+        NxJson {
+            target_defaults: target_defaults.as_object().cloned().unwrap(),
+            ..NxJson::default()
+        }
+    }
+
+    #[test]
+    fn executor_defaults_win_over_name_and_glob_defaults() {
+        let nx_json = nx_json(json!({
+            "@nx/vite:build": {"options": {"from": "executor"}},
+            "build": {"options": {"from": "name"}},
+            "build-*": {"options": {"from": "glob"}}
+        }));
+        let mut targets = json!({
+            "build": {"executor": "@nx/vite:build"},
+            "compile": {"executor": "@nx/js:tsc"},
+            "build-storybook": {}
+        })
+        .as_object()
+        .cloned()
+        .unwrap();
+        nx_json.apply_target_defaults(&mut targets);
+
+        assert_eq!(targets["build"]["options"]["from"], "executor");
+        assert_eq!(targets["compile"].get("options"), None);
+        assert_eq!(targets["build-storybook"]["options"]["from"], "glob");
+    }
+
+    #[test]
+    fn the_target_wins_over_its_defaults() {
+        let nx_json = nx_json(json!({
+            "test": {"cache": true, "options": {"ci": true, "coverage": true}}
+        }));
+        let mut targets = json!({"test": {"options": {"coverage": false}}})
+            .as_object()
+            .cloned()
+            .unwrap();
+        nx_json.apply_target_defaults(&mut targets);
+
+        assert_eq!(
+            targets["test"],
+            json!({"cache": true, "options": {"ci": true, "coverage": false}})
+        );
+    }
+}
@@ -0,0 +1,218 @@
+use serde_json::{json, Map, Value};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use super::project::{ProjectError, ProjectType};
+use super::targets::merge_targets;
+
+// What a package.json contributes to a project, either on its own or on top of a project.json
+#[derive(Debug)]
+pub struct PackageJson {
+    pub name: Option<String>,
+    pub project_type: Option<ProjectType>,
+    pub targets: Map<String, Value>,
+    pub has_nx_config: bool,
+    // The "nx" block as written, Null when there isn't one
+    pub nx: Value,
+    pub has_scripts: bool,
+    // Every declared dependency (including dev, peer and optional ones) with its version range
+    pub dependencies: BTreeMap<String, String>,
+}
+
+impl PackageJson {
+    pub fn read(package_json_path: &Path) -> Result<PackageJson, ProjectError> {
+        let content = fs::read_to_string(package_json_path)?;
+        let v: Value = serde_json::from_str(&content)?;
+        let nx = v.get("nx");
+
+        let name = nx
+            .and_then(|n| n.get("name"))
+            .or_else(|| v.get("name"))
+            .and_then(|n| n.as_str())
+            .map(|n| n.to_string());
+
+        let project_type = nx
+            .and_then(|n| n.get("projectType"))
+            .and_then(|t| t.as_str())
+            .map(|t| match t {
+                "library" => ProjectType::Library,
+                _other => ProjectType::Application,
+            });
+
+        let scripts = v.get("scripts").and_then(|s| s.as_object());
+
+        // nx only turns the listed scripts into targets when includedScripts is present
+        let included_scripts: Option<Vec<&str>> = nx
+            .and_then(|n| n.get("includedScripts"))
+            .and_then(|s| s.as_array())
+            .map(|s| s.iter().filter_map(|s| s.as_str()).collect());
+
+        let mut targets: Map<String, Value> = scripts
+            .into_iter()
+            .flat_map(|s| s.keys())
+            .filter(|script| match &included_scripts {
+                Some(included) => included.contains(&script.as_str()),
+                None => true,
+            })
+            .map(|script| {
+                let target =
+                    json!({ "executor": "nx:run-script", "options": { "script": script } });
+                (script.to_string(), target)
+            })
+            .collect();
+
+        if let Some(nx_targets) = nx
+            .and_then(|n| n.get("targets"))
+            .and_then(|t| t.as_object())
+        {
+            merge_targets(&mut targets, nx_targets);
+        }
+
+        let dependencies = [
+            "dependencies",
+            "devDependencies",
+            "peerDependencies",
+            "optionalDependencies",
+        ]
+        .iter()
+        .filter_map(|key| v.get(key).and_then(|d| d.as_object()))
+        .flatten()
+        .map(|(name, range)| (name.clone(), range.as_str().unwrap_or("").to_string()))
+        .collect();
+
+        Ok(PackageJson {
+            name,
+            project_type,
+            targets,
+            has_nx_config: nx.is_some(),
+            nx: nx.cloned().unwrap_or_default(),
+            has_scripts: scripts.is_some_and(|s| !s.is_empty()),
+            dependencies,
+        })
+    }
+
+    // A package.json without a project.json next to it only counts as a project if nx would
+    // pick it up, and the workspace root only counts when it opts in with an "nx" block
+    pub fn is_standalone_project(&self, is_workspace_root: bool) -> bool {
+        if is_workspace_root {
+            return self.has_nx_config;
+        }
+        self.has_nx_config || self.has_scripts
+    }
+}
+
+// nx falls back to the workspace layout when a package.json doesn't say what it is
+pub fn infer_project_type(base_repo_path: &Path, project_path: &Path) -> ProjectType {
+    let relative = project_path
+        .strip_prefix(base_repo_path)
+        .unwrap_or(project_path);
+    match relative.components().next() {
+        Some(first) if first.as_os_str() == "apps" => ProjectType::Application,
+        _ => ProjectType::Library,
+    }
+}
+
+// The version of `package` a project gets: whatever node would resolve from node_modules walking
+// up to the workspace root, or failing that whatever the project or the root declares
+pub fn resolve_version(
+    base_repo_path: &Path,
+    project_path: &Path,
+    package: &str,
+) -> Option<String> {
+    let installed = installed_manifest(base_repo_path, project_path, package).and_then(|path| {
+        let v: Value = serde_json::from_str(&fs::read_to_string(path).ok()?).ok()?;
+        v["version"].as_str().map(|v| v.to_string())
+    });
+
+    installed
+        .or_else(|| declared_version(&project_path.join("package.json"), package))
+        .or_else(|| declared_version(&base_repo_path.join("package.json"), package))
+        .and_then(|version| short_version(&version))
+}
+
+// The package.json of the copy of `package` node would load from the project
+pub fn installed_manifest(
+    base_repo_path: &Path,
+    project_path: &Path,
+    package: &str,
+) -> Option<PathBuf> {
+    project_path
+        .ancestors()
+        .take_while(|dir| dir.starts_with(base_repo_path))
+        .map(|dir| dir.join("node_modules").join(package).join("package.json"))
+        .find(|manifest| manifest.is_file())
+}
+
+fn declared_version(package_json_path: &Path, package: &str) -> Option<String> {
+    PackageJson::read(package_json_path)
+        .ok()?
+        .dependencies
+        .remove(package)
+}
+
+// "^17.3.2" -> "17.3", ranges and tags without a number in them give nothing
+fn short_version(version: &str) -> Option<String> {
+    let start = version.find(|c: char| c.is_ascii_digit())?;
+    let parts: Vec<&str> = version[start..]
+        .split(|c: char| !c.is_ascii_digit())
+        .take(2)
+        .filter(|part| !part.is_empty())
+        .collect();
+    Some(parts.join("."))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(content: &str) -> PackageJson {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("package.json");
+        fs::write(&path, content).unwrap();
+        PackageJson::read(&path).unwrap()
+    }
+
+    #[test]
+    fn included_scripts_limits_which_scripts_become_targets() {
+        let package_json = read(
+            r#"{
+                "name": "web",
+                "scripts": {"build": "vite build", "test": "vitest", "postinstall": "patch"},
+                "nx": {
+                    "includedScripts": ["build", "test"],
+                    "targets": {"test": {"cache": true}, "lint": {"executor": "@nx/eslint:lint"}}
+                }
+            }"#,
+        );
+
+        let targets: Vec<&str> = package_json.targets.keys().map(|k| k.as_str()).collect();
+        assert_eq!(targets, vec!["build", "lint", "test"]);
+        assert_eq!(
+            package_json.targets["test"],
+            json!({"executor": "nx:run-script", "options": {"script": "test"}, "cache": true})
+        );
+    }
+
+    #[test]
+    fn every_script_is_a_target_without_included_scripts() {
+        let package_json = read(r#"{"name": "web", "scripts": {"build": "", "test": ""}}"#);
+        assert_eq!(package_json.targets.len(), 2);
+        assert!(!package_json.has_nx_config);
+    }
+
+    #[test]
+    fn the_root_only_counts_with_an_nx_block() {
+        let scripts_only = read(r#"{"name": "ws", "scripts": {"build": "nx run-many -t build"}}"#);
+        assert!(!scripts_only.is_standalone_project(true));
+        assert!(scripts_only.is_standalone_project(false));
+
+        let opted_in = read(r#"{"name": "ws", "nx": {}}"#);
+        assert!(opted_in.is_standalone_project(true));
+
+        let neither = read(r#"{"name": "types", "dependencies": {}}"#);
+        assert!(!neither.is_standalone_project(false));
+    }
+}
@@ -1,18 +1,34 @@
-use serde_json::Value;
-use std::{fs, path::Path};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::mpsc::Sender,
+    thread,
+};
 
 use std::error::Error;
 use std::fmt;
 
+use crate::state::State;
 use crate::utils::find_files;
 
-use super::frameworks::KNOWN_FRAMEWORKS;
+use super::cache::{self, CacheEntry, ProjectCache};
+use super::frameworks::FrameworkRegistry;
+use super::graph;
+use super::matchers::DeepDetectionMatcher;
+use super::nx_json::NxJson;
+use super::package_json::{infer_project_type, resolve_version, PackageJson};
+use super::targets::merge_targets;
 
 #[derive(Debug)]
 pub enum ProjectError {
     IoError(std::io::Error),
     JsonParseError(serde_json::Error),
     MissingField(&'static str),
+    UnreadableFile(PathBuf, std::io::Error),
+    InvalidUtf8(PathBuf),
+    InvalidMatcher(String),
 }
 
 impl fmt::Display for ProjectError {
@@ -21,6 +37,64 @@ impl fmt::Display for ProjectError {
             Self::IoError(e) => write!(f, "IO error: {}", e),
             Self::JsonParseError(e) => write!(f, "JSON parsing error: {}", e),
             Self::MissingField(field) => write!(f, "Missing required field: {}", field),
+            Self::UnreadableFile(path, e) => {
+                write!(f, "Could not read {}: {}", path.display(), e)
+            }
+            Self::InvalidUtf8(path) => write!(f, "{} is not valid UTF-8", path.display()),
+            Self::InvalidMatcher(e) => write!(f, "Invalid deep detection matcher for {}", e),
+        }
+    }
+}
+
+impl ProjectError {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::IoError(_) => "io",
+            Self::JsonParseError(_) => "invalid json",
+            Self::MissingField(_) => "missing field",
+            Self::UnreadableFile(_, _) => "unreadable",
+            Self::InvalidUtf8(_) => "invalid utf-8",
+            Self::InvalidMatcher(_) => "invalid matcher",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+// A problem found while scanning, along with the file it's about
+#[derive(Debug)]
+pub struct Diagnostic {
+    pub path: PathBuf,
+    pub severity: Severity,
+    pub error: ProjectError,
+}
+
+impl Diagnostic {
+    pub fn error(path: &Path, error: ProjectError) -> Diagnostic {
+        Diagnostic {
+            path: path.to_path_buf(),
+            severity: Severity::Error,
+            error,
+        }
+    }
+
+    pub fn warning(path: &Path, error: ProjectError) -> Diagnostic {
+        Diagnostic {
+            path: path.to_path_buf(),
+            severity: Severity::Warning,
+            error,
+        }
+    }
+
+    // Line and column of the problem, only known for JSON syntax errors
+    pub fn location(&self) -> Option<(usize, usize)> {
+        match &self.error {
+            ProjectError::JsonParseError(e) => Some((e.line(), e.column())),
+            _ => None,
         }
     }
 }
@@ -31,36 +105,95 @@ pub enum ProjectType {
     Library,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Task {
     pub command: String,
     pub subcommands: Vec<String>,
-}
-#[derive(Debug)]
-pub struct DeepDetectionMatcher {
-    pub path: &'static str,
-    pub keyword: &'static str,
+    // e.g. `@nx/vite:build` or `nx:run-commands`, None when the target doesn't say
+    pub executor: Option<String>,
+    pub options: Map<String, Value>,
+    // Option overrides per configuration, keyed by the names in `subcommands`
+    pub configurations: Map<String, Value>,
+    pub default_configuration: Option<String>,
+    pub depends_on: Vec<Value>,
+    pub inputs: Vec<Value>,
+    pub outputs: Vec<String>,
+    // None means the target doesn't say, so nx's default applies
+    pub cache: Option<bool>,
 }
 
-#[derive(Clone, Copy, Debug)]
+// Definitions come from frameworks.json plus the user's and workspace's own files, see
+// FrameworkRegistry for how they're combined
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Framework {
-    pub name: &'static str,
-    pub identity_files: &'static [&'static str],
-    pub proj_identity_keywords: &'static [&'static str],
-    pub deep_detection_matchers: &'static [DeepDetectionMatcher],
-    pub commands: &'static [&'static str],
+    pub name: String,
+    #[serde(default)]
+    pub identity_files: Vec<String>,
+    #[serde(default)]
+    pub proj_identity_keywords: Vec<String>,
+    #[serde(default)]
+    pub deep_detection_matchers: Vec<DeepDetectionMatcher>,
+    // npm packages that mark a project as using the framework when it depends on them
+    #[serde(default)]
+    pub packages: Vec<String>,
+    // Executors (`@nx/vite:build`) or whole executor packages (`@nx/vite`) that mark a project as
+    // using the framework when one of its targets runs them
+    #[serde(default)]
+    pub executors: Vec<String>,
+    #[serde(default)]
+    pub commands: Vec<String>,
+    // Where the framework goes among the others, higher first. Built-ins are all 0, so a user or
+    // workspace definition needs a positive priority to be picked as a project's primary one.
+    #[serde(default)]
+    pub priority: i32,
 }
 
-#[derive(Debug)]
+// A framework a project was found to use, with the installed (or declared) version of its
+// `packages` when one could be found
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DetectedFramework {
+    pub framework: Framework,
+    pub version: Option<String>,
+}
+
+#[derive(Debug, Clone)]
 pub struct Project {
     pub name: String,
     pub project_type: ProjectType,
+    // Relative to the workspace, "." for a project at the root
+    pub root: String,
+    pub source_root: Option<String>,
+    pub tags: Vec<String>,
+    pub implicit_dependencies: Vec<String>,
+    pub named_inputs: Map<String, Value>,
     pub tasks: Vec<Task>,
-    pub framework: Option<Framework>,
+    // Registry order, which puts the most specific (primary) framework first
+    pub frameworks: Vec<DetectedFramework>,
+}
+
+// Progress reported by Project::detect while it scans
+#[derive(Debug)]
+pub enum ScanEvent {
+    // The walk is done and this many config files are about to be parsed
+    Started { total: usize },
+    // One config file was parsed, with the project it defines (if any)
+    Scanned(Option<Project>),
+    // Something was wrong with a file, sent before the Scanned event of the project it belongs to
+    Diagnostic(Diagnostic),
+    // The nx project graph came through and supersedes everything scanned so far
+    Replaced(Vec<Project>),
+    Finished,
 }
 
 impl Error for ProjectError {}
 
+// Like fs::read_to_string, but the error says which file it was about
+fn read_file(path: &Path) -> Result<String, ProjectError> {
+    let bytes = fs::read(path).map_err(|e| ProjectError::UnreadableFile(path.to_path_buf(), e))?;
+    String::from_utf8(bytes).map_err(|_| ProjectError::InvalidUtf8(path.to_path_buf()))
+}
+
 // Some helpful conversions
 impl From<std::io::Error> for ProjectError {
     fn from(err: std::io::Error) -> Self {
@@ -75,138 +208,639 @@ impl From<serde_json::Error> for ProjectError {
 }
 
 impl Project {
-    pub fn detect(base_repo_path: &Path) -> Vec<Project> {
-        let mut projects = Vec::new();
-        let project_json_paths = find_files(base_repo_path, &["project.json"]);
-
-        for project_json_path in project_json_paths {
-            let containing_path = match Path::new(&project_json_path).parent() {
-                Some(path) => path,
-                None => continue,
-            };
-
-            // Handle the Result from parse_config
-            match Project::parse_config(&project_json_path) {
-                Ok(mut project) => {
-                    project.framework = Self::detect_framework(&containing_path);
-                    if project.framework.is_none() {
-                        project.framework = Self::deep_detect_framework(&containing_path);
-                    }
-                    projects.push(project);
-                }
-                Err(e) => {
-                    eprintln!("Failed to parse project {}: {}", project_json_path, e);
-                    continue;
-                }
+    // Scans the workspace, reporting each project on `events` as soon as it's parsed. The files
+    // give a fast first pass while nx works out the project graph alongside it. If nx manages
+    // one, it replaces the walk's projects wholesale once the walk is done, since it also knows
+    // about targets inferred by nx plugins.
+    pub fn detect(base_repo_path: &Path, events: &Sender<ScanEvent>) {
+        let mut diagnostics = Vec::new();
+        let frameworks = FrameworkRegistry::load(base_repo_path, &mut diagnostics);
+
+        let cache = ProjectCache::load(base_repo_path);
+
+        let (scanned, graph_projects) = thread::scope(|scope| {
+            let graph = scope.spawn(|| {
+                let mut diagnostics = Vec::new();
+                let projects = graph::load(base_repo_path, &frameworks, &mut diagnostics);
+                (projects, diagnostics)
+            });
+            let scanned = Self::detect_from_files(base_repo_path, &frameworks, &cache, events);
+            let (graph_projects, graph_diagnostics) = graph.join().unwrap_or_default();
+            diagnostics.extend(graph_diagnostics);
+            (scanned, graph_projects)
+        });
+
+        let walked_projects = scanned.iter().map(|(e, _)| e.project.clone()).collect();
+        // Projects with problems are left out of the cache so they get reported again
+        let entries: Vec<CacheEntry> = scanned
+            .into_iter()
+            .filter(|(_, has_diagnostics)| !has_diagnostics)
+            .map(|(e, _)| e)
+            .collect();
+
+        let projects = match graph_projects {
+            Some(projects) => {
+                let _ = events.send(ScanEvent::Replaced(projects.clone()));
+                projects
             }
+            None => walked_projects,
+        };
+
+        for diagnostic in diagnostics {
+            let _ = events.send(ScanEvent::Diagnostic(diagnostic));
         }
 
-        projects
+        ProjectCache::save(base_repo_path, &entries, &projects);
+        let _ = events.send(ScanEvent::Finished);
     }
 
-    fn detect_framework(project_path: &Path) -> Option<Framework> {
-        for framework in KNOWN_FRAMEWORKS {
-            for identity_file in framework.identity_files {
-                let identity_file_path = project_path.join(identity_file);
-                if identity_file_path.exists() {
-                    return Some(framework.clone());
-                }
-            }
+    // Whatever the last scan found, for showing something while a fresh scan runs
+    pub fn detect_cached(base_repo_path: &Path) -> Vec<Project> {
+        ProjectCache::load(base_repo_path).projects()
+    }
 
-            if !framework.proj_identity_keywords.is_empty()
-                && fs::read_to_string(project_path.join("project.json"))
-                    .expect("Failed to read project.json file")
-                    .contains(framework.proj_identity_keywords.iter().next().unwrap())
-            {
-                return Some(framework.clone());
-            }
-        }
+    // Returns every project found along with whether it had any diagnostics
+    fn detect_from_files(
+        base_repo_path: &Path,
+        frameworks: &FrameworkRegistry,
+        cache: &ProjectCache,
+        events: &Sender<ScanEvent>,
+    ) -> Vec<(CacheEntry, bool)> {
+        let nx_json_path = base_repo_path.join("nx.json");
+        let nx_json = NxJson::read(base_repo_path).unwrap_or_else(|e| {
+            let _ = events.send(ScanEvent::Diagnostic(Diagnostic::error(&nx_json_path, e)));
+            NxJson::default()
+        });
+
+        let mut ignore_globs = State::global().lock().ignore_globs.clone();
+        ignore_globs.extend(nx_json.ignore_globs());
+
+        let config_paths = find_files(
+            base_repo_path,
+            &["project.json", "package.json"],
+            &ignore_globs,
+        );
+        let _ = events.send(ScanEvent::Started {
+            total: config_paths.len(),
+        });
+
+        // Parsing is mostly file reads, so spread the config files over a few threads
+        let threads = thread::available_parallelism().map_or(1, |n| n.get());
+        let chunk_size = config_paths.len().div_ceil(threads).max(1);
+
+        thread::scope(|scope| {
+            let workers: Vec<_> = config_paths
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    let nx_json = &nx_json;
+                    let events = events.clone();
+                    scope.spawn(move || {
+                        chunk
+                            .iter()
+                            .filter_map(|path| {
+                                let mut diagnostics = Vec::new();
+                                let entry = Self::detect_one(
+                                    base_repo_path,
+                                    Path::new(path),
+                                    nx_json,
+                                    frameworks,
+                                    cache,
+                                    &mut diagnostics,
+                                );
 
-        None
+                                let has_diagnostics = !diagnostics.is_empty();
+                                for diagnostic in diagnostics {
+                                    let _ = events.send(ScanEvent::Diagnostic(diagnostic));
+                                }
+                                let project = entry.as_ref().map(|e| e.project.clone());
+                                let _ = events.send(ScanEvent::Scanned(project));
+
+                                entry.map(|e| (e, has_diagnostics))
+                            })
+                            .collect::<Vec<(CacheEntry, bool)>>()
+                    })
+                })
+                .collect();
+
+            workers
+                .into_iter()
+                .flat_map(|worker| worker.join().unwrap_or_default())
+                .collect()
+        })
     }
 
-    fn deep_detect_framework(project_path: &Path) -> Option<Framework> {
-        for framework in KNOWN_FRAMEWORKS {
-            for matcher in framework.deep_detection_matchers {
-                let matcher_path = project_path.join(&matcher.path);
-                if matcher_path.exists() {
-                    let matcher_content =
-                        fs::read_to_string(&matcher_path).expect("Failed to read matcher file");
-                    if matcher_content.contains(&matcher.keyword) {
-                        return Some(framework.clone());
-                    }
-                }
+    fn detect_one(
+        base_repo_path: &Path,
+        config_path: &Path,
+        nx_json: &NxJson,
+        frameworks: &FrameworkRegistry,
+        cache: &ProjectCache,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) -> Option<CacheEntry> {
+        let containing_path = config_path.parent()?;
+
+        // A project.json always wins, its package.json gets merged into it by parse_config
+        let is_package_json = config_path.file_name() == Some("package.json".as_ref());
+        if is_package_json && containing_path.join("project.json").exists() {
+            return None;
+        }
+
+        let source = config_path.to_string_lossy().to_string();
+        let fingerprint = cache::fingerprint(base_repo_path, config_path, frameworks);
+        if let Some(project) = cache.lookup(&source, &fingerprint) {
+            return Some(CacheEntry {
+                source,
+                fingerprint,
+                project: project.clone(),
+            });
+        }
+
+        let parsed = if is_package_json {
+            Project::parse_package_json(base_repo_path, config_path, nx_json)
+        } else {
+            Project::parse_config(base_repo_path, config_path, nx_json, diagnostics).map(Some)
+        };
+
+        // Handle the Result from parse_config
+        match parsed {
+            Ok(Some(project)) => Some(CacheEntry {
+                source,
+                fingerprint,
+                project: project.with_frameworks(
+                    base_repo_path,
+                    containing_path,
+                    frameworks,
+                    diagnostics,
+                ),
+            }),
+            Ok(None) => None,
+            Err(e) => {
+                diagnostics.push(Diagnostic::error(config_path, e));
+                None
             }
         }
+    }
 
-        None
+    // Anything that went wrong while looking for frameworks ends up in `diagnostics`, detection
+    // just carries on with the next framework
+    pub fn with_frameworks(
+        mut self,
+        base_repo_path: &Path,
+        project_path: &Path,
+        frameworks: &FrameworkRegistry,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) -> Project {
+        self.frameworks = Self::detect_frameworks(
+            base_repo_path,
+            project_path,
+            &self.tasks,
+            frameworks,
+            diagnostics,
+        );
+        self
     }
 
-    fn get_tasks(project_json_content: &String) -> Option<Vec<Task>> {
-        // Try to parse the JSON, if it fails -> peace out with None
-        let v: Value = match serde_json::from_str(project_json_content) {
-            Ok(parsed) => parsed,
-            Err(_) => return None,
+    // Every framework that matches, in registry order, along with the version in use
+    fn detect_frameworks(
+        base_repo_path: &Path,
+        project_path: &Path,
+        tasks: &[Task],
+        frameworks: &FrameworkRegistry,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) -> Vec<DetectedFramework> {
+        // Keywords are matched against whichever file defines the project
+        let project_json_path = project_path.join("project.json");
+        let config_path = if project_json_path.exists() {
+            project_json_path
+        } else {
+            project_path.join("package.json")
         };
-
-        let tasks = match v.get("targets").and_then(|t| t.as_object()) {
-            Some(task_map) => task_map,
-            None => return None,
+        let project_config = if config_path.exists() {
+            read_file(&config_path).unwrap_or_else(|e| {
+                diagnostics.push(Diagnostic::error(&config_path, e));
+                String::new()
+            })
+        } else {
+            String::new()
         };
 
-        let parsed_tasks: Option<Vec<Task>> = tasks
+        // Only the project's own dependencies count, in an integrated repo the root package.json
+        // depends on every framework in the workspace. The root is still used to find versions.
+        let dependencies = PackageJson::read(&project_path.join("package.json"))
+            .map(|p| p.dependencies)
+            .unwrap_or_default();
+
+        frameworks
             .iter()
-            .map(|(key, value)| {
-                if value.get("configurations") != None {
-                    let subcommands = value["configurations"]
-                        .as_object()
-                        .unwrap()
+            .filter(|framework| {
+                Self::matches_identity(framework, project_path, &project_config)
+                    || framework
+                        .packages
                         .iter()
-                        .map(|(key, _)| key.to_string())
-                        .collect();
-                    return Some(Task {
-                        command: key.to_string(),
-                        subcommands,
-                    });
-                }
-                Some(Task {
-                    command: key.to_string(),
-                    subcommands: vec![],
+                        .any(|package| dependencies.contains_key(package))
+                    || Self::matches_executor(framework, tasks)
+                    || Self::matches_deep(framework, project_path, diagnostics)
+            })
+            .map(|framework| DetectedFramework {
+                framework: framework.clone(),
+                version: framework
+                    .packages
+                    .iter()
+                    .find_map(|package| resolve_version(base_repo_path, project_path, package)),
+            })
+            .collect()
+    }
+
+    fn matches_identity(framework: &Framework, project_path: &Path, project_config: &str) -> bool {
+        let has_identity_file = framework
+            .identity_files
+            .iter()
+            .any(|identity_file| project_path.join(identity_file).exists());
+
+        let has_keyword = framework
+            .proj_identity_keywords
+            .iter()
+            .any(|keyword| project_config.contains(keyword.as_str()));
+
+        has_identity_file || has_keyword
+    }
+
+    fn matches_executor(framework: &Framework, tasks: &[Task]) -> bool {
+        tasks
+            .iter()
+            .filter_map(|task| task.executor.as_deref())
+            // Nx 16 renamed its plugins from @nrwl to @nx, older workspaces still use the old names
+            .map(|executor| match executor.strip_prefix("@nrwl/") {
+                Some(rest) => format!("@nx/{}", rest),
+                None => executor.to_string(),
+            })
+            .any(|executor| {
+                framework.executors.iter().any(|e| {
+                    executor == *e
+                        || executor
+                            .strip_prefix(e.as_str())
+                            .is_some_and(|rest| rest.starts_with(':'))
                 })
             })
-            .collect(); // collect will return None if any task conversion failed
+    }
 
-        parsed_tasks
+    fn matches_deep(
+        framework: &Framework,
+        project_path: &Path,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) -> bool {
+        framework
+            .deep_detection_matchers
+            .iter()
+            .any(|matcher| matcher.matches(project_path, diagnostics))
     }
 
-    fn parse_config(project_json_path: &String) -> Result<Project, ProjectError> {
+    // Problems that don't stop the project from being usable end up in `diagnostics`
+    fn parse_config(
+        base_repo_path: &Path,
+        project_json_path: &Path,
+        nx_json: &NxJson,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) -> Result<Project, ProjectError> {
         let project_config = fs::read_to_string(project_json_path)?;
 
         let v: Value = serde_json::from_str(&project_config)?;
 
+        // nx reads a package.json sitting next to a project.json as part of the same project
+        let package_json_path = project_json_path.with_file_name("package.json");
+        let package_json = if package_json_path.exists() {
+            PackageJson::read(&package_json_path)
+                .map_err(|e| diagnostics.push(Diagnostic::error(&package_json_path, e)))
+                .ok()
+        } else {
+            None
+        };
+
         let name = v["name"]
             .as_str()
-            .ok_or(ProjectError::MissingField("name"))?
-            .to_string();
+            .map(|n| n.to_string())
+            .or_else(|| package_json.as_ref().and_then(|p| p.name.clone()))
+            .ok_or(ProjectError::MissingField("name"))?;
 
-        let project_type = v["projectType"]
-            .as_str()
-            .ok_or(ProjectError::MissingField("projectType"))
-            .map(|t| match t {
-                "library" => ProjectType::Library,
-                _other => ProjectType::Application,
-            })?;
-
-        let tasks = Self::get_tasks(&project_config)
-            .unwrap_or_default()
-            .into_iter()
-            .collect();
+        // Without a projectType we guess from the workspace layout, like nx does for package.json
+        let project_type = match v["projectType"].as_str() {
+            Some("library") => ProjectType::Library,
+            Some(_other) => ProjectType::Application,
+            None => {
+                diagnostics.push(Diagnostic::warning(
+                    project_json_path,
+                    ProjectError::MissingField("projectType"),
+                ));
+                let project_path = project_json_path.parent().unwrap_or(base_repo_path);
+                infer_project_type(base_repo_path, project_path)
+            }
+        };
+
+        // Like targets, metadata from the package.json's nx block is overridden by project.json
+        let package_nx = package_json
+            .as_ref()
+            .map(|p| p.nx.clone())
+            .unwrap_or_default();
+        let mut named_inputs = object(&package_nx, "namedInputs");
+        named_inputs.extend(object(&v, "namedInputs"));
 
+        let mut targets = package_json.map(|p| p.targets).unwrap_or_default();
+        if let Some(project_targets) = v.get("targets").and_then(|t| t.as_object()) {
+            merge_targets(&mut targets, project_targets);
+        }
+        nx_json.apply_target_defaults(&mut targets);
+        let tasks = Task::from_targets(&Value::Object(targets));
+
+        let project_path = project_json_path.parent().unwrap_or(base_repo_path);
         Ok(Project {
             name,
             project_type,
+            root: project_root(base_repo_path, project_path),
+            source_root: v["sourceRoot"]
+                .as_str()
+                .or(package_nx["sourceRoot"].as_str())
+                .map(|s| s.to_string()),
+            tags: union(string_list(&package_nx, "tags"), string_list(&v, "tags")),
+            implicit_dependencies: union(
+                string_list(&package_nx, "implicitDependencies"),
+                string_list(&v, "implicitDependencies"),
+            ),
+            named_inputs,
             tasks,
-            framework: None,
+            frameworks: vec![],
         })
     }
+
+    fn parse_package_json(
+        base_repo_path: &Path,
+        package_json_path: &Path,
+        nx_json: &NxJson,
+    ) -> Result<Option<Project>, ProjectError> {
+        let mut package_json = PackageJson::read(package_json_path)?;
+        let project_path = package_json_path.parent().unwrap_or(base_repo_path);
+
+        let is_workspace_root = project_path == base_repo_path;
+        if !package_json.is_standalone_project(is_workspace_root) {
+            return Ok(None);
+        }
+
+        let project_type = package_json
+            .project_type
+            .unwrap_or_else(|| infer_project_type(base_repo_path, project_path));
+        nx_json.apply_target_defaults(&mut package_json.targets);
+
+        let nx = &package_json.nx;
+        Ok(Some(Project {
+            name: package_json
+                .name
+                .ok_or(ProjectError::MissingField("name"))?,
+            project_type,
+            root: project_root(base_repo_path, project_path),
+            source_root: nx["sourceRoot"].as_str().map(|s| s.to_string()),
+            tags: string_list(nx, "tags"),
+            implicit_dependencies: string_list(nx, "implicitDependencies"),
+            named_inputs: object(nx, "namedInputs"),
+            tasks: Task::from_targets(&Value::Object(package_json.targets)),
+            frameworks: vec![],
+        }))
+    }
+}
+
+// The workspace-relative directory of a project, the way nx writes it
+pub fn project_root(base_repo_path: &Path, project_path: &Path) -> String {
+    let relative = project_path
+        .strip_prefix(base_repo_path)
+        .unwrap_or(project_path)
+        .to_string_lossy()
+        .to_string();
+    if relative.is_empty() {
+        ".".to_string()
+    } else {
+        relative
+    }
+}
+
+// A list of strings such as "tags", anything that isn't a string is skipped
+pub fn string_list(config: &Value, key: &str) -> Vec<String> {
+    config[key]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|s| s.as_str().map(|s| s.to_string()))
+        .collect()
+}
+
+pub fn object(config: &Value, key: &str) -> Map<String, Value> {
+    config[key].as_object().cloned().unwrap_or_default()
+}
+
+// nx concatenates list metadata from several sources rather than replacing it
+fn union(mut base: Vec<String>, additions: Vec<String>) -> Vec<String> {
+    for item in additions {
+        if !base.contains(&item) {
+            base.push(item);
+        }
+    }
+    base
+}
+
+impl Task {
+    // Builds tasks out of a nx "targets" object, as found in project.json or package.json's nx block
+    pub fn from_targets(targets: &Value) -> Vec<Task> {
+        let Some(task_map) = targets.as_object() else {
+            return vec![];
+        };
+
+        task_map
+            .iter()
+            .map(|(key, value)| {
+                let configurations = value
+                    .get("configurations")
+                    .and_then(|c| c.as_object())
+                    .cloned()
+                    .unwrap_or_default();
+                let subcommands = configurations.keys().map(|key| key.to_string()).collect();
+
+                let mut options = value
+                    .get("options")
+                    .and_then(|o| o.as_object())
+                    .cloned()
+                    .unwrap_or_default();
+
+                // `"command": "..."` on the target itself is nx shorthand for nx:run-commands
+                let executor = match (value.get("executor"), value.get("command")) {
+                    (Some(executor), _) => executor.as_str().map(|e| e.to_string()),
+                    (None, Some(command)) => {
+                        options.insert("command".to_string(), command.clone());
+                        Some("nx:run-commands".to_string())
+                    }
+                    (None, None) => None,
+                };
+
+                let list = |field: &str| {
+                    value
+                        .get(field)
+                        .and_then(|v| v.as_array())
+                        .cloned()
+                        .unwrap_or_default()
+                };
+
+                Task {
+                    command: key.to_string(),
+                    subcommands,
+                    executor,
+                    options,
+                    configurations,
+                    default_configuration: value
+                        .get("defaultConfiguration")
+                        .and_then(|c| c.as_str())
+                        .map(|c| c.to_string()),
+                    depends_on: list("dependsOn"),
+                    inputs: list("inputs"),
+                    outputs: list("outputs")
+                        .iter()
+                        .filter_map(|o| o.as_str().map(|o| o.to_string()))
+                        .collect(),
+                    cache: value.get("cache").and_then(|c| c.as_bool()),
+                }
+            })
+            .collect()
+    }
+
+    // The options a run would get, with the configuration's overrides on top
+    pub fn options_for(&self, configuration: Option<&str>) -> Map<String, Value> {
+        let mut options = self.options.clone();
+        let overrides = configuration
+            .or(self.default_configuration.as_deref())
+            .and_then(|c| self.configurations.get(c))
+            .and_then(|c| c.as_object());
+        if let Some(overrides) = overrides {
+            options.extend(overrides.clone());
+        }
+        options
+    }
+
+    // The shell commands an nx:run-commands target runs, in order
+    pub fn shell_commands(&self, configuration: Option<&str>) -> Vec<String> {
+        if self.executor.as_deref() != Some("nx:run-commands") {
+            return vec![];
+        }
+
+        let options = self.options_for(configuration);
+        let args = options.get("args").and_then(|a| a.as_str());
+        let commands = match (options.get("command"), options.get("commands")) {
+            (Some(command), _) => vec![command.clone()],
+            (None, Some(Value::Array(commands))) => commands.clone(),
+            _ => vec![],
+        };
+
+        commands
+            .iter()
+            // Each entry is either the command itself or an object with a `command` field
+            .filter_map(|c| c.as_str().or_else(|| c.get("command")?.as_str()))
+            .map(|command| match args {
+                Some(args) => format!("{} {}", command, args),
+                None => command.to_string(),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_env;
+
+    #[test]
+    fn a_project_json_wins_over_the_package_json_next_to_it() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("apps/web")).unwrap();
+        fs::write(
+            root.join("package.json"),
+            r#"{"name": "ws", "scripts": {"build": "nx run-many -t build"}}"#,
+        )
+        .unwrap();
+        fs::write(
+            root.join("apps/web/project.json"),
+            r#"{"name": "web", "projectType": "application", "targets": {"build": {}}}"#,
+        )
+        .unwrap();
+        fs::write(
+            root.join("apps/web/package.json"),
+            r#"{"name": "@ws/web", "scripts": {"dev": "vite"}}"#,
+        )
+        .unwrap();
+
+        // The fingerprint takes in the user's framework files from XDG_CONFIG_HOME
+        let _env = test_env::lock();
+        let frameworks = FrameworkRegistry::builtin();
+        let detect = |path: &str| {
+            let mut diagnostics = Vec::new();
+            let entry = Project::detect_one(
+                root,
+                &root.join(path),
+                &NxJson::default(),
+                &frameworks,
+                &ProjectCache::default(),
+                &mut diagnostics,
+            );
+            assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+            entry.map(|e| e.project)
+        };
+
+        assert!(detect("apps/web/package.json").is_none());
+        // The root's scripts alone don't make it a project
+        assert!(detect("package.json").is_none());
+
+        let web = detect("apps/web/project.json").unwrap();
+        assert_eq!(web.name, "web");
+        let mut targets: Vec<&str> = web.tasks.iter().map(|t| t.command.as_str()).collect();
+        targets.sort();
+        assert_eq!(targets, vec!["build", "dev"]);
+    }
+
+    #[test]
+    fn root_dependencies_alone_dont_tag_a_project() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::write(
+            root.join("package.json"),
+            r#"{"name": "ws", "devDependencies": {"next": "^14.1.0"}}"#,
+        )
+        .unwrap();
+        fs::create_dir_all(root.join("libs/ui")).unwrap();
+        fs::create_dir_all(root.join("apps/site")).unwrap();
+        fs::write(
+            root.join("apps/site/package.json"),
+            r#"{"name": "site", "dependencies": {"next": "*"}}"#,
+        )
+        .unwrap();
+        fs::create_dir_all(root.join("node_modules/next")).unwrap();
+        fs::write(
+            root.join("node_modules/next/package.json"),
+            r#"{"name": "next", "version": "14.1.4"}"#,
+        )
+        .unwrap();
+
+        let detect = |project: &str| {
+            Project::detect_frameworks(
+                root,
+                &root.join(project),
+                &[],
+                &FrameworkRegistry::builtin(),
+                &mut Vec::new(),
+            )
+        };
+
+        assert!(detect("libs/ui").is_empty());
+
+        // A project that depends on it itself is, with the version that's installed
+        let site = detect("apps/site");
+        let nextjs = site
+            .iter()
+            .find(|f| f.framework.name == "nextjs")
+            .expect("nextjs detected from the project's package.json");
+        assert_eq!(nextjs.version.as_deref(), Some("14.1"));
+    }
 }
@@ -0,0 +1,127 @@
+use serde_json::{Map, Value};
+
+// Merges one definition of a target on top of another, the same way nx combines a target coming
+// from several places (package.json, project.json, targetDefaults).
+pub fn merge_target(base: &Value, overrides: &Value) -> Value {
+    let (Some(base_map), Some(override_map)) = (base.as_object(), overrides.as_object()) else {
+        return overrides.clone();
+    };
+
+    // A different executor means a different target altogether, nothing from the base carries over
+    let base_executor = base_map.get("executor").and_then(|e| e.as_str());
+    let override_executor = override_map.get("executor").and_then(|e| e.as_str());
+    if let (Some(a), Some(b)) = (base_executor, override_executor) {
+        if a != b {
+            return overrides.clone();
+        }
+    }
+
+    let mut merged = base_map.clone();
+    for (key, value) in override_map {
+        let combined = match (key.as_str(), merged.get(key)) {
+            ("options", Some(existing)) => merge_objects(existing, value),
+            ("configurations", Some(existing)) => merge_configurations(existing, value),
+            _ => value.clone(),
+        };
+        merged.insert(key.clone(), combined);
+    }
+
+    Value::Object(merged)
+}
+
+// Merges every target in `overrides` into `targets`, adding the ones that don't exist yet
+pub fn merge_targets(targets: &mut Map<String, Value>, overrides: &Map<String, Value>) {
+    for (name, target) in overrides {
+        let merged = match targets.get(name) {
+            Some(existing) => merge_target(existing, target),
+            None => target.clone(),
+        };
+        targets.insert(name.clone(), merged);
+    }
+}
+
+fn merge_objects(base: &Value, overrides: &Value) -> Value {
+    match (base.as_object(), overrides.as_object()) {
+        (Some(base_map), Some(override_map)) => {
+            let mut merged = base_map.clone();
+            for (key, value) in override_map {
+                merged.insert(key.clone(), value.clone());
+            }
+            Value::Object(merged)
+        }
+        _ => overrides.clone(),
+    }
+}
+
+// Configurations with the same name get their options merged rather than replaced
+fn merge_configurations(base: &Value, overrides: &Value) -> Value {
+    match (base.as_object(), overrides.as_object()) {
+        (Some(base_map), Some(override_map)) => {
+            let mut merged = base_map.clone();
+            for (name, configuration) in override_map {
+                let combined = match merged.get(name) {
+                    Some(existing) => merge_objects(existing, configuration),
+                    None => configuration.clone(),
+                };
+                merged.insert(name.clone(), combined);
+            }
+            Value::Object(merged)
+        }
+        _ => overrides.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn options_and_configurations_merge_with_the_override_winning() {
+        let base = json!({
+            "executor": "@nx/vite:build",
+            "cache": true,
+            "options": {"outputPath": "dist", "mode": "development"},
+            "configurations": {"production": {"mode": "production", "minify": true}}
+        });
+        let overrides = json!({
+            "options": {"mode": "test"},
+            "configurations": {"production": {"minify": false}, "ci": {}}
+        });
+
+        assert_eq!(
+            merge_target(&base, &overrides),
+            json!({
+                "executor": "@nx/vite:build",
+                "cache": true,
+                "options": {"outputPath": "dist", "mode": "test"},
+                "configurations": {
+                    "production": {"mode": "production", "minify": false},
+                    "ci": {}
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn a_different_executor_replaces_the_target() {
+        let base = json!({"executor": "@nx/jest:jest", "options": {"ci": true}});
+        let overrides = json!({"executor": "@nx/vite:test"});
+        assert_eq!(merge_target(&base, &overrides), overrides);
+    }
+
+    #[test]
+    fn merge_targets_adds_missing_targets() {
+        let mut targets = json!({"build": {"options": {"a": 1}}})
+            .as_object()
+            .cloned()
+            .unwrap();
+        let overrides = json!({"build": {"options": {"b": 2}}, "lint": {}});
+        merge_targets(&mut targets, overrides.as_object().unwrap());
+
+        assert_eq!(
+            Value::Object(targets),
+            json!({"build": {"options": {"a": 1, "b": 2}}, "lint": {}})
+        );
+    }
+}
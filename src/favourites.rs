@@ -0,0 +1,55 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::utils::workspace_state_dir;
+
+const FAVOURITES_FILE: &str = "favourites.json";
+
+// The nx commands pinned to the top of the list in a workspace, in the order they were pinned
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Favourites {
+    pinned: Vec<String>,
+}
+
+impl Favourites {
+    pub fn load(base_repo_path: &Path) -> Favourites {
+        favourites_file(base_repo_path)
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn contains(&self, target: &str) -> bool {
+        self.pinned.iter().any(|t| t == target)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &String> {
+        self.pinned.iter()
+    }
+
+    // Pins the target, or unpins it if it already was, and saves straight away
+    pub fn toggle(&mut self, base_repo_path: &Path, target: &str) {
+        if self.contains(target) {
+            self.pinned.retain(|t| t != target);
+        } else {
+            self.pinned.push(target.to_string());
+        }
+
+        let Some(path) = favourites_file(base_repo_path) else {
+            return;
+        };
+        if let Some(dir) = path.parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+        if let Ok(content) = serde_json::to_string(self) {
+            let _ = fs::write(path, content);
+        }
+    }
+}
+
+fn favourites_file(base_repo_path: &Path) -> Option<PathBuf> {
+    workspace_state_dir(base_repo_path).map(|dir| dir.join(FAVOURITES_FILE))
+}
@@ -4,19 +4,61 @@ pub enum Flag {
     Debug,
     Verbose,
     Error,
+    Ignore(String),
+    Parallel(String),
+    Base(String),
 }
 
-pub fn parse_args(args: &Vec<String>) -> Vec<Flag> {
+pub fn parse_args(args: &[String]) -> Vec<Flag> {
     let mut flags = Vec::new();
-    for arg in args {
+    let mut args_iter = args.iter();
+    while let Some(arg) = args_iter.next() {
         match arg.as_str() {
             "-h" | "--help" => flags.push(Flag::Help),
             "-v" | "--version" => flags.push(Flag::Version),
             "-d" | "--debug" => flags.push(Flag::Debug),
             "-V" | "--verbose" => flags.push(Flag::Verbose),
             "-E" | "--error" => flags.push(Flag::Error),
-            _ => (),
+            "-I" | "--ignore" => {
+                if let Some(glob) = args_iter.next() {
+                    flags.push(Flag::Ignore(glob.clone()));
+                }
+            }
+            "-p" | "--parallel" => {
+                if let Some(parallel) = args_iter.next() {
+                    flags.push(Flag::Parallel(parallel.clone()));
+                }
+            }
+            "-b" | "--base" => {
+                if let Some(base) = args_iter.next() {
+                    flags.push(Flag::Base(base.clone()));
+                }
+            }
+            other => {
+                if let Some(glob) = other.strip_prefix("--ignore=") {
+                    flags.push(Flag::Ignore(glob.to_string()));
+                } else if let Some(parallel) = other.strip_prefix("--parallel=") {
+                    flags.push(Flag::Parallel(parallel.to_string()));
+                } else if let Some(base) = other.strip_prefix("--base=") {
+                    flags.push(Flag::Base(base.to_string()));
+                }
+            }
         }
     }
     flags
 }
+
+// The first argument that isn't a flag (or a flag's value) is the workspace to explore
+pub fn search_path(args: &[String]) -> Option<&str> {
+    let mut args_iter = args.iter().skip(1);
+    while let Some(arg) = args_iter.next() {
+        match arg.as_str() {
+            "-I" | "--ignore" | "-p" | "--parallel" | "-b" | "--base" => {
+                args_iter.next();
+            }
+            other if other.starts_with('-') => (),
+            other => return Some(other),
+        }
+    }
+    None
+}
@@ -0,0 +1,178 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::utils::workspace_state_dir;
+
+const HISTORY_FILE: &str = "history.json";
+
+// Old runs barely count towards frecency anyway, this just stops the file growing forever
+const MAX_RUNS: usize = 500;
+
+const DAY: u64 = 24 * 60 * 60;
+
+// One target's part in a command launched from the list, be it `nx run`, `nx run-many` or
+// `nx affected`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Run {
+    // The target as `nx run` would take it, e.g. web:build:production
+    pub target: String,
+    // Seconds since the epoch
+    pub timestamp: u64,
+    // None when nx was killed by a signal
+    pub exit_code: Option<i32>,
+    pub duration_ms: u64,
+    // Extra arguments added in the prompt or the options form, as they were passed
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub args: String,
+}
+
+// Every launch in a workspace, oldest first
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct History {
+    runs: Vec<Run>,
+}
+
+impl History {
+    // History is a nicety, a missing or broken file just means starting over
+    pub fn load(base_repo_path: &Path) -> History {
+        history_file(base_repo_path)
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    // Written out straight away, so the run is kept even if the app is closed without a clean exit
+    pub fn record(&mut self, base_repo_path: &Path, run: Run) {
+        self.runs.push(run);
+        if self.runs.len() > MAX_RUNS {
+            self.runs.drain(..self.runs.len() - MAX_RUNS);
+        }
+
+        let Some(path) = history_file(base_repo_path) else {
+            return;
+        };
+        if let Some(dir) = path.parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+        if let Ok(content) = serde_json::to_string(self) {
+            let _ = fs::write(path, content);
+        }
+    }
+
+    // Distinct arguments previously added to `target`, most recent first
+    pub fn previous_args(&self, target: &str) -> Vec<String> {
+        let mut args: Vec<String> = Vec::new();
+        for run in self.runs.iter().rev() {
+            if run.target == target && !run.args.is_empty() && !args.contains(&run.args) {
+                args.push(run.args.clone());
+            }
+        }
+        args
+    }
+
+    // Frecency of every target that's been run: each run adds points, fewer the older it is
+    pub fn frecency(&self, now: u64) -> HashMap<String, u64> {
+        let mut scores = HashMap::new();
+        for run in &self.runs {
+            *scores.entry(run.target.clone()).or_default() +=
+                recency_weight(now.saturating_sub(run.timestamp));
+        }
+        scores
+    }
+}
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+// The same buckets Firefox uses for its address bar
+fn recency_weight(age_secs: u64) -> u64 {
+    match age_secs / DAY {
+        0..4 => 100,
+        4..14 => 70,
+        14..31 => 50,
+        31..90 => 30,
+        _ => 10,
+    }
+}
+
+fn history_file(base_repo_path: &Path) -> Option<PathBuf> {
+    workspace_state_dir(base_repo_path).map(|dir| dir.join(HISTORY_FILE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn run(target: &str, days_ago: u64, args: &str) -> Run {
+        Run {
+            target: target.to_string(),
+            timestamp: NOW - days_ago * DAY,
+            exit_code: Some(0),
+            duration_ms: 1000,
+            args: args.to_string(),
+        }
+    }
+
+    #[test]
+    fn frecency_adds_up_runs_weighted_by_age() {
+        let history = History {
+            runs: vec![
+                run("web:build", 100, ""),
+                run("web:build", 20, ""),
+                run("web:build", 1, ""),
+                run("api:serve", 5, ""),
+                run("api:serve", 3, ""),
+                // Clock skew shouldn't make a run count for nothing
+                Run {
+                    timestamp: NOW + 60,
+                    ..run("web:lint", 0, "")
+                },
+            ],
+        };
+
+        let scores = history.frecency(NOW);
+        assert_eq!(scores["web:build"], 10 + 50 + 100);
+        assert_eq!(scores["api:serve"], 70 + 100);
+        assert_eq!(scores["web:lint"], 100);
+        assert!(!scores.contains_key("docs:build"));
+    }
+
+    #[test]
+    fn recency_buckets_change_on_day_boundaries() {
+        assert_eq!(recency_weight(4 * DAY - 1), 100);
+        assert_eq!(recency_weight(4 * DAY), 70);
+        assert_eq!(recency_weight(14 * DAY), 50);
+        assert_eq!(recency_weight(31 * DAY), 30);
+        assert_eq!(recency_weight(90 * DAY), 10);
+    }
+
+    #[test]
+    fn previous_args_are_distinct_and_most_recent_first() {
+        let history = History {
+            runs: vec![
+                run("web:build", 3, "--verbose"),
+                run("web:build", 2, "--skip-nx-cache"),
+                run("api:build", 2, "--prod"),
+                run("web:build", 1, "--verbose"),
+                run("web:build", 0, ""),
+            ],
+        };
+
+        assert_eq!(
+            history.previous_args("web:build"),
+            vec!["--verbose", "--skip-nx-cache"]
+        );
+    }
+}
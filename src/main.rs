@@ -1,5 +1,7 @@
 mod detection;
+mod favourites;
 mod flags;
+mod history;
 mod state;
 mod ui;
 mod utils;
@@ -35,6 +37,9 @@ fn main() {
                 println!("  -d, --debug    Enable debug mode");
                 println!("  -V, --verbose  Enable verbose mode");
                 println!("  -E, --error    Enable error mode");
+                println!("  -I, --ignore   Skip paths matching a glob while scanning (repeatable)");
+                println!("  -p, --parallel How many tasks nx runs at once for marked commands");
+                println!("  -b, --base     Git ref affected mode compares against");
                 return;
             }
             flags::Flag::Verbose => {
@@ -45,6 +50,18 @@ fn main() {
                 let mut store = state.lock();
                 store.error_enabled = true;
             }
+            flags::Flag::Ignore(glob) => {
+                let mut store = state.lock();
+                store.ignore_globs.push(glob);
+            }
+            flags::Flag::Parallel(parallel) => {
+                let mut store = state.lock();
+                store.parallel = Some(parallel);
+            }
+            flags::Flag::Base(base) => {
+                let mut store = state.lock();
+                store.base = Some(base);
+            }
         }
     }
 
@@ -64,7 +81,7 @@ fn main() {
         return;
     }
 
-    let search_path_str = args.get(1).map(String::as_str).unwrap_or(".");
+    let search_path_str = flags::search_path(&args).unwrap_or(".");
     project_paths_check(search_path_str);
 
     let _ = ui::terminal::setup();
@@ -77,7 +94,7 @@ fn project_paths_check(search_path_str: &str) {
     let resolved_search_path = search_path.canonicalize().unwrap();
     let resolved_search_path_str = resolved_search_path.to_str().unwrap();
 
-    if !path_exists(&search_path) {
+    if !path_exists(search_path) {
         eprintln!("The path \"{}\" does not exist.", &resolved_search_path_str);
         std::process::exit(1);
     }
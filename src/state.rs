@@ -1,10 +1,15 @@
-use std::sync::{Arc, Mutex, Once};
+use std::sync::{Arc, Mutex, OnceLock};
 
 #[derive(Debug, Default)]
 
 pub struct Store {
     pub verbose_enabled: bool,
     pub error_enabled: bool,
+    pub ignore_globs: Vec<String>,
+    // Passed on as `nx run-many --parallel`, nx's own default applies when unset
+    pub parallel: Option<String>,
+    // Base ref for affected mode, nx.json's defaultBase applies when unset
+    pub base: Option<String>,
 }
 
 pub struct State {
@@ -13,23 +18,15 @@ pub struct State {
 
 impl State {
     pub fn global() -> &'static State {
-        // We use Once to ensure the initialization happens exactly once
-        static ONCE: Once = Once::new();
-        static mut INSTANCE: Option<State> = None;
+        // We use OnceLock to ensure the initialization happens exactly once
+        static INSTANCE: OnceLock<State> = OnceLock::new();
 
-        unsafe {
-            ONCE.call_once(|| {
-                // Initialize the singleton instance
-                let state = State {
-                    store: Arc::new(Mutex::new(Store::default())),
-                };
-                INSTANCE = Some(state);
-            });
-            INSTANCE.as_ref().unwrap()
-        }
+        INSTANCE.get_or_init(|| State {
+            store: Arc::new(Mutex::new(Store::default())),
+        })
     }
 
-    pub fn lock(&self) -> std::sync::MutexGuard<Store> {
+    pub fn lock(&self) -> std::sync::MutexGuard<'_, Store> {
         self.store.lock().unwrap()
     }
 }
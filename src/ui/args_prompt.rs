@@ -0,0 +1,182 @@
+use ratatui::{prelude::*, widgets::*};
+use tui_textarea::{CursorMove, Input, Key, TextArea};
+
+// What the user did with the prompt after a key press
+pub enum PromptAction {
+    Editing,
+    Cancel,
+    // The arguments split into words, and as typed
+    Run(Vec<String>, String),
+}
+
+// Lets the user add arguments to a composed nx command before it's launched. The command is shown
+// in front of the textarea but can't be edited, so whatever runs is still that command. Up and
+// down step through arguments used with it before.
+pub struct ArgsPrompt {
+    prefix: String,
+    textarea: TextArea<'static>,
+    // Most recent first
+    previous_args: Vec<String>,
+    history_index: Option<usize>,
+}
+
+impl ArgsPrompt {
+    pub fn new(command: &str, previous_args: Vec<String>) -> ArgsPrompt {
+        let mut prompt = ArgsPrompt {
+            prefix: command.to_string(),
+            textarea: TextArea::default(),
+            previous_args,
+            history_index: None,
+        };
+        prompt.set_args("");
+        prompt
+    }
+
+    pub fn input(&mut self, input: Input) -> PromptAction {
+        match input {
+            Input { key: Key::Esc, .. } => return PromptAction::Cancel,
+            Input {
+                key: Key::Enter, ..
+            } => {
+                let args = self.textarea.lines()[0].trim().to_string();
+                return PromptAction::Run(split_words(&args), args);
+            }
+            Input { key: Key::Up, .. } => self.step_history(1),
+            Input { key: Key::Down, .. } => self.step_history(-1),
+            input => {
+                self.textarea.input(input);
+            }
+        }
+        PromptAction::Editing
+    }
+
+    // Moves `step` entries further back (positive) or forward (negative) through the history,
+    // forward past the newest entry goes back to no arguments
+    fn step_history(&mut self, step: isize) {
+        if self.previous_args.is_empty() {
+            return;
+        }
+
+        let next = match self.history_index {
+            None if step > 0 => Some(0),
+            None => None,
+            Some(i) => i
+                .checked_add_signed(step)
+                .filter(|&i| i < self.previous_args.len())
+                .or(if step > 0 { Some(i) } else { None }),
+        };
+
+        self.history_index = next;
+        let args = next
+            .map(|i| self.previous_args[i].clone())
+            .unwrap_or_default();
+        self.set_args(&args);
+    }
+
+    fn set_args(&mut self, args: &str) {
+        self.textarea = TextArea::new(vec![args.to_string()]);
+        self.textarea.move_cursor(CursorMove::End);
+        self.textarea.set_cursor_line_style(Style::default());
+    }
+
+    pub fn render(&mut self, frame: &mut Frame, area: Rect) {
+        let width = 100.min(area.width);
+        let height = 3.min(area.height);
+        let popup = Rect {
+            x: area.x + (area.width - width) / 2,
+            y: area.y + (area.height - height) / 2,
+            width,
+            height,
+        };
+
+        let hint = match self.previous_args.len() {
+            0 => " enter to run, esc to cancel ".to_string(),
+            n => format!(" enter to run, esc to cancel, up/down for {} previous ", n),
+        };
+        let block = Block::bordered()
+            .title("Arguments")
+            .title_bottom(Span::styled(hint, Style::default().fg(Color::DarkGray)));
+        let inner = block.inner(popup);
+        let [prefix_area, args_area] = Layout::horizontal([
+            Constraint::Length(self.prefix.chars().count() as u16 + 1),
+            Constraint::Min(0),
+        ])
+        .areas(inner);
+
+        frame.render_widget(Clear, popup);
+        frame.render_widget(block, popup);
+        frame.render_widget(
+            Span::styled(&self.prefix, Style::default().fg(Color::Gray)),
+            prefix_area,
+        );
+        frame.render_widget(&self.textarea, args_area);
+    }
+}
+
+// Splits a command line into words the way a shell would for simple cases: whitespace separates,
+// single and double quotes group, backslash escapes the next character outside single quotes
+pub fn split_words(line: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut in_word = false;
+    let mut quote = None;
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('"'), '\\') | (None, '\\') => {
+                if let Some(escaped) = chars.next() {
+                    current.push(escaped);
+                }
+                in_word = true;
+            }
+            (Some(_), c) => current.push(c),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_word = true;
+            }
+        }
+    }
+
+    if in_word {
+        words.push(current);
+    }
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn whitespace_separates_words() {
+        assert_eq!(
+            split_words("  --watch   --port=4300\t--verbose "),
+            vec!["--watch", "--port=4300", "--verbose"]
+        );
+        assert!(split_words("   ").is_empty());
+    }
+
+    #[test]
+    fn quotes_group_and_backslashes_escape() {
+        assert_eq!(
+            split_words(r#"--grep="login form" --name='it''s' --path=a\ b"#),
+            vec!["--grep=login form", "--name=its", "--path=a b"]
+        );
+        assert_eq!(
+            split_words(r#""say \"hi\"" 'no \escape' "" x"#),
+            vec![r#"say "hi""#, r"no \escape", "", "x"]
+        );
+    }
+}
@@ -0,0 +1,138 @@
+use crate::detection::project::{Project, Task};
+use ratatui::{prelude::*, widgets::*};
+use serde_json::Value;
+
+// Everything the project config says about the selected target, with targetDefaults already
+// merged in by the scan, followed by the project-wide settings that affect it. `configuration`
+// is the selected row's configuration, if any.
+pub fn render(
+    frame: &mut Frame,
+    area: Rect,
+    title: &str,
+    project: Option<&Project>,
+    task: Option<&Task>,
+    configuration: Option<&str>,
+) {
+    let block = Block::bordered().title(format!("Target {}", title));
+
+    let mut lines = match task {
+        Some(task) => task_lines(task, configuration),
+        None => vec![Line::from(Span::styled(
+            "Not a target of this project, nx will resolve it when run",
+            Style::default().fg(Color::DarkGray),
+        ))],
+    };
+    if let Some(project) = project {
+        lines.extend(project_lines(project));
+    }
+
+    let paragraph = Paragraph::new(lines)
+        .wrap(Wrap { trim: false })
+        .block(block);
+    frame.render_widget(paragraph, area);
+}
+
+fn task_lines(task: &Task, configuration: Option<&str>) -> Vec<Line<'static>> {
+    let mut lines = vec![field(
+        "Executor",
+        task.executor.as_deref().unwrap_or("(none)"),
+    )];
+
+    let commands = task.shell_commands(configuration);
+    if !commands.is_empty() {
+        lines.push(heading("Command"));
+        lines.extend(commands.into_iter().map(|command| {
+            Line::from(Span::styled(
+                format!("  {}", command),
+                Style::default().fg(Color::LightYellow),
+            ))
+        }));
+    }
+
+    let configurations = match &task.default_configuration {
+        Some(default) => format!("{} (default {})", task.subcommands.join(", "), default),
+        None => task.subcommands.join(", "),
+    };
+    lines.push(field("Configurations", &configurations));
+
+    let cache = match task.cache {
+        Some(true) => "yes",
+        Some(false) => "no",
+        None => "default",
+    };
+    lines.push(field("Cache", cache));
+    lines.push(field("Depends on", &describe_all(&task.depends_on)));
+    lines.push(field("Inputs", &describe_all(&task.inputs)));
+    lines.push(field("Outputs", &task.outputs.join(", ")));
+
+    lines.push(heading("Options"));
+    let options = task.options_for(configuration);
+    if options.is_empty() {
+        lines.push(Line::from(Span::styled(
+            "  (none)",
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+    for (key, value) in options {
+        lines.push(entry(&key, &value));
+    }
+    lines
+}
+
+fn project_lines(project: &Project) -> Vec<Line<'static>> {
+    let mut lines = vec![
+        Line::from(""),
+        heading("Project"),
+        field("Root", &project.root),
+        field("Source root", project.source_root.as_deref().unwrap_or("")),
+        field("Implicit deps", &project.implicit_dependencies.join(", ")),
+    ];
+
+    if !project.named_inputs.is_empty() {
+        lines.push(heading("Named inputs"));
+        for (name, value) in &project.named_inputs {
+            lines.push(entry(name, value));
+        }
+    }
+    lines
+}
+
+fn entry(key: &str, value: &Value) -> Line<'static> {
+    Line::from(vec![
+        Span::styled(format!("  {}: ", key), Style::default().fg(Color::Gray)),
+        Span::styled(describe(value), Style::default().fg(Color::White)),
+    ])
+}
+
+fn heading(label: &str) -> Line<'static> {
+    Line::from(Span::styled(
+        label.to_string(),
+        Style::default().fg(Color::LightBlue),
+    ))
+}
+
+fn field(label: &str, value: &str) -> Line<'static> {
+    Line::from(vec![
+        Span::styled(
+            format!("{:16}", label),
+            Style::default().fg(Color::LightBlue),
+        ),
+        Span::styled(value.to_string(), Style::default().fg(Color::White)),
+    ])
+}
+
+fn describe_all(values: &[Value]) -> String {
+    values
+        .iter()
+        .map(describe)
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+// Strings as they are, anything else as compact JSON
+fn describe(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
@@ -0,0 +1,45 @@
+use crate::detection::project::{Diagnostic, Severity};
+use ratatui::{prelude::*, widgets::*};
+use std::path::Path;
+
+// Lists everything that went wrong during the scan, paths shown relative to the workspace
+pub fn render(frame: &mut Frame, area: Rect, diagnostics: &[Diagnostic], search_path: &Path) {
+    let items: Vec<ListItem> = diagnostics
+        .iter()
+        .map(|diagnostic| {
+            let (label, color) = match diagnostic.severity {
+                Severity::Error => ("error", Color::Red),
+                Severity::Warning => ("warning", Color::Yellow),
+            };
+
+            let path = diagnostic
+                .path
+                .strip_prefix(search_path)
+                .unwrap_or(&diagnostic.path);
+            let location = match diagnostic.location() {
+                Some((line, column)) => format!("{}:{}:{}", path.display(), line, column),
+                None => path.display().to_string(),
+            };
+
+            ListItem::new(Line::from(vec![
+                Span::styled(format!("{:10}", label), Style::default().fg(color)),
+                Span::styled(
+                    format!("{:50} ", location),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(
+                    format!("{:16}", diagnostic.error.kind()),
+                    Style::default().fg(Color::LightBlue),
+                ),
+                Span::styled(
+                    diagnostic.error.to_string(),
+                    Style::default().fg(Color::Gray),
+                ),
+            ]))
+        })
+        .collect();
+
+    let title = format!("Diagnostics ({})", diagnostics.len());
+    let list = List::new(items).block(Block::bordered().title(title));
+    frame.render_widget(list, area);
+}
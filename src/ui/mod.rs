@@ -1 +1,7 @@
+mod args_prompt;
+mod details;
+mod diagnostics;
+mod option_form;
+mod query;
+mod runner;
 pub mod terminal;
@@ -0,0 +1,302 @@
+use ratatui::{prelude::*, widgets::*};
+use serde_json::{Map, Value};
+use tui_textarea::{Input, Key};
+
+use crate::detection::executor_schema::{ExecutorOption, OptionKind};
+
+// What the user did with the form after a key press
+pub enum FormAction {
+    Editing,
+    Cancel,
+    // `--option=value` for every option changed from what the project already passes
+    Run(Vec<String>),
+}
+
+struct FormField {
+    option: ExecutorOption,
+    // What the target runs with today, as it would be written on the command line
+    initial: String,
+    value: String,
+}
+
+impl FormField {
+    fn is_changed(&self) -> bool {
+        self.value != self.initial
+    }
+
+    // Enum values to step through, with a blank first when the target doesn't set one
+    fn choices(&self) -> Vec<String> {
+        let OptionKind::Enum(values) = &self.option.kind else {
+            return vec![];
+        };
+        let mut choices = values.clone();
+        if !values.contains(&self.initial) {
+            choices.insert(0, self.initial.clone());
+        }
+        choices
+    }
+
+    fn step(&mut self, step: isize) {
+        match self.option.kind {
+            OptionKind::Boolean => self.toggle(),
+            OptionKind::Enum(_) => {
+                let choices = self.choices();
+                let current = choices.iter().position(|c| *c == self.value).unwrap_or(0);
+                let next = (current as isize + step).rem_euclid(choices.len() as isize);
+                self.value = choices[next as usize].clone();
+            }
+            _ => (),
+        }
+    }
+
+    fn toggle(&mut self) {
+        self.value = if self.value == "true" {
+            "false"
+        } else {
+            "true"
+        }
+        .to_string();
+    }
+
+    // Only text and numbers are typed, the rest can only be set to one of their values
+    fn backspace(&mut self) {
+        if matches!(self.option.kind, OptionKind::String | OptionKind::Number) {
+            self.value.pop();
+        }
+    }
+
+    fn type_char(&mut self, c: char) {
+        match self.option.kind {
+            OptionKind::String => self.value.push(c),
+            OptionKind::Number if c.is_ascii_digit() || c == '.' || c == '-' => self.value.push(c),
+            _ => (),
+        }
+    }
+
+    fn value_display(&self, selected: bool) -> String {
+        match self.option.kind {
+            OptionKind::Boolean if self.value == "true" => "[x]".to_string(),
+            OptionKind::Boolean => "[ ]".to_string(),
+            OptionKind::Enum(_) => format!("< {} >", self.value),
+            _ if selected => format!("{}▏", self.value),
+            _ => self.value.clone(),
+        }
+    }
+}
+
+// Edits a target's options as described by its executor's schema. Fields start out with what
+// project.json passes for the configuration being run, or the schema's default otherwise.
+pub struct OptionForm {
+    title: String,
+    fields: Vec<FormField>,
+    selection: ListState,
+}
+
+impl OptionForm {
+    pub fn new(
+        title: &str,
+        options: Vec<ExecutorOption>,
+        current: &Map<String, Value>,
+    ) -> OptionForm {
+        let fields = options
+            .into_iter()
+            .map(|option| {
+                let initial = current
+                    .get(&option.name)
+                    .or(option.default.as_ref())
+                    .map(value_string)
+                    .unwrap_or_else(|| match option.kind {
+                        OptionKind::Boolean => "false".to_string(),
+                        _ => String::new(),
+                    });
+                FormField {
+                    option,
+                    value: initial.clone(),
+                    initial,
+                }
+            })
+            .collect();
+
+        let mut selection = ListState::default();
+        selection.select(Some(0));
+        OptionForm {
+            title: title.to_string(),
+            fields,
+            selection,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    pub fn input(&mut self, input: Input) -> FormAction {
+        let index = self.selection.selected().unwrap_or(0);
+        let len = self.fields.len();
+
+        match input {
+            Input { key: Key::Esc, .. } => return FormAction::Cancel,
+            Input {
+                key: Key::Enter, ..
+            } => return FormAction::Run(self.overrides()),
+            Input { key: Key::Down, .. } | Input { key: Key::Tab, .. } => {
+                self.selection.select(Some((index + 1) % len))
+            }
+            Input { key: Key::Up, .. } => self.selection.select(Some((index + len - 1) % len)),
+            Input { key: Key::Left, .. } => self.fields[index].step(-1),
+            Input {
+                key: Key::Right, ..
+            } => self.fields[index].step(1),
+            Input {
+                key: Key::Backspace,
+                ..
+            } => self.fields[index].backspace(),
+            Input {
+                key: Key::Char(' '),
+                ..
+            } if self.fields[index].option.kind == OptionKind::Boolean => {
+                self.fields[index].toggle()
+            }
+            Input {
+                key: Key::Char(c),
+                ctrl: false,
+                alt: false,
+                ..
+            } => self.fields[index].type_char(c),
+            _ => (),
+        }
+        FormAction::Editing
+    }
+
+    fn overrides(&self) -> Vec<String> {
+        self.fields
+            .iter()
+            .filter(|field| field.is_changed())
+            .map(|field| format!("--{}={}", field.option.name, field.value))
+            .collect()
+    }
+
+    pub fn render(&mut self, frame: &mut Frame, area: Rect) {
+        let width = 100.min(area.width);
+        // Room for every field plus the borders and the description line
+        let height = (self.fields.len() as u16 + 3).min(area.height.saturating_sub(4));
+        let popup = Rect {
+            x: area.x + (area.width - width) / 2,
+            y: area.y + (area.height - height) / 2,
+            width,
+            height,
+        };
+
+        let selected = self.selection.selected().unwrap_or(0);
+        let items: Vec<ListItem> = self
+            .fields
+            .iter()
+            .enumerate()
+            .map(|(i, field)| {
+                let changed = if field.is_changed() { "* " } else { "  " };
+                ListItem::new(Line::from(vec![
+                    Span::styled(changed, Style::default().fg(Color::LightCyan)),
+                    Span::styled(
+                        format!("{:32} ", field.option.name),
+                        Style::default().fg(Color::LightGreen),
+                    ),
+                    Span::styled(
+                        field.value_display(i == selected),
+                        Style::default().fg(Color::LightYellow),
+                    ),
+                ]))
+            })
+            .collect();
+
+        let hint = match self.overrides().len() {
+            0 => " enter to run, esc to cancel ".to_string(),
+            n => format!(" enter to run with {} changed, esc to cancel ", n),
+        };
+        let block = Block::bordered()
+            .title(format!("Options for {}", self.title))
+            .title_bottom(Span::styled(hint, Style::default().fg(Color::DarkGray)));
+        let inner = block.inner(popup);
+        let [list_area, description_area] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(inner);
+
+        let description = self
+            .fields
+            .get(selected)
+            .and_then(|field| field.option.description.clone())
+            .unwrap_or_default();
+
+        frame.render_widget(Clear, popup);
+        frame.render_widget(block, popup);
+        frame.render_stateful_widget(
+            List::new(items).highlight_style(
+                Style::default()
+                    .bg(Color::DarkGray)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            list_area,
+            &mut self.selection,
+        );
+        frame.render_widget(
+            Paragraph::new(Span::styled(description, Style::default().fg(Color::Gray))),
+            description_area,
+        );
+    }
+}
+
+// How a project.json or schema value is written after `--option=`
+fn value_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        value => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn option(name: &str, kind: OptionKind) -> ExecutorOption {
+        ExecutorOption {
+            name: name.to_string(),
+            kind,
+            description: None,
+            default: None,
+        }
+    }
+
+    fn key(key: Key) -> Input {
+        Input {
+            key,
+            ..Input::default()
+        }
+    }
+
+    #[test]
+    fn only_changed_options_are_passed_and_backspace_leaves_choices_alone() {
+        let options = vec![
+            option("watch", OptionKind::Boolean),
+            option(
+                "mode",
+                OptionKind::Enum(vec!["development".into(), "production".into()]),
+            ),
+            option("port", OptionKind::Number),
+            option("outputPath", OptionKind::String),
+        ];
+        let current = json!({"mode": "production", "port": 4200, "outputPath": "dist"});
+        let mut form = OptionForm::new("web:serve", options, current.as_object().unwrap());
+
+        // A boolean and an enum field, neither of which backspace can truncate
+        form.input(key(Key::Backspace));
+        form.input(key(Key::Down));
+        form.input(key(Key::Backspace));
+        form.input(key(Key::Down));
+        form.input(key(Key::Backspace));
+        form.input(key(Key::Char('1')));
+
+        let FormAction::Run(overrides) = form.input(key(Key::Enter)) else {
+            panic!("enter should run");
+        };
+        assert_eq!(overrides, vec!["--port=4201"]);
+    }
+}
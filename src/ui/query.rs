@@ -0,0 +1,220 @@
+use std::fmt;
+
+// What a `key:value` filter in the search box looks at
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Field {
+    Type,
+    Framework,
+    Tag,
+    Target,
+    Configuration,
+    // A quoted phrase or a negated word, matched against the whole row
+    Text,
+}
+
+#[derive(Debug, Clone)]
+pub struct Filter {
+    pub field: Field,
+    pub value: String,
+    pub negated: bool,
+}
+
+// A parsed search box: filters every row has to pass, and whatever's left over to fuzzy match
+#[derive(Debug, Default)]
+pub struct Query {
+    pub filters: Vec<Filter>,
+    pub text: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum QueryError {
+    UnclosedQuote,
+    MissingValue(String),
+    InvalidType(String),
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnclosedQuote => write!(f, "Unclosed quote"),
+            Self::MissingValue(key) => write!(f, "{}: needs a value", key),
+            Self::InvalidType(value) => {
+                write!(f, "type: expects app or lib, not {:?}", value)
+            }
+        }
+    }
+}
+
+impl Query {
+    // `type:lib fw:angular tag:scope:payments target:test cfg:production`, `-` in front of any
+    // term to exclude it and double quotes for phrases. Words that aren't filters (including
+    // `project:target` style ones) are fuzzy matched like before.
+    pub fn parse(input: &str) -> Result<Query, QueryError> {
+        let mut query = Query::default();
+        let mut words = Vec::new();
+
+        for token in tokenize(input)? {
+            let (negated, term) = match token.strip_prefix('-') {
+                Some(rest) if !rest.is_empty() => (true, rest),
+                _ => (false, token.as_str()),
+            };
+
+            if let Some(phrase) = term.strip_prefix('"') {
+                query.filters.push(Filter {
+                    field: Field::Text,
+                    value: phrase.trim_end_matches('"').to_lowercase(),
+                    negated,
+                });
+                continue;
+            }
+
+            let filter = term
+                .split_once(':')
+                .and_then(|(key, value)| field(key).map(|field| (key, field, value)));
+            match filter {
+                Some((key, field, value)) => {
+                    let value = value.trim_matches('"').to_lowercase();
+                    if value.is_empty() {
+                        return Err(QueryError::MissingValue(key.to_string()));
+                    }
+                    if field == Field::Type && project_type(&value).is_none() {
+                        return Err(QueryError::InvalidType(value));
+                    }
+                    query.filters.push(Filter {
+                        field,
+                        value,
+                        negated,
+                    });
+                }
+                None if negated => query.filters.push(Filter {
+                    field: Field::Text,
+                    value: term.to_lowercase(),
+                    negated,
+                }),
+                None => words.push(term.to_string()),
+            }
+        }
+
+        query.text = words.join(" ");
+        Ok(query)
+    }
+}
+
+// Normalises the values `type:` accepts to "app" or "lib"
+pub fn project_type(value: &str) -> Option<&'static str> {
+    match value {
+        "app" | "application" => Some("app"),
+        "lib" | "library" => Some("lib"),
+        _ => None,
+    }
+}
+
+fn field(key: &str) -> Option<Field> {
+    match key {
+        "type" => Some(Field::Type),
+        "fw" | "framework" => Some(Field::Framework),
+        "tag" => Some(Field::Tag),
+        "target" => Some(Field::Target),
+        "cfg" | "configuration" => Some(Field::Configuration),
+        _ => None,
+    }
+}
+
+// Splits on whitespace outside of double quotes, keeping the quotes so phrases can be told apart
+fn tokenize(input: &str) -> Result<Vec<String>, QueryError> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+
+    for c in input.chars() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                current.push(c);
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+
+    if in_quotes {
+        return Err(QueryError::UnclosedQuote);
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    Ok(tokens)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filters(query: &Query) -> Vec<(Field, &str, bool)> {
+        query
+            .filters
+            .iter()
+            .map(|f| (f.field, f.value.as_str(), f.negated))
+            .collect()
+    }
+
+    #[test]
+    fn filters_are_pulled_out_and_the_rest_is_fuzzy_text() {
+        let query =
+            Query::parse("type:library fw:Angular tag:scope:payments web:build cfg:prod").unwrap();
+
+        assert_eq!(
+            filters(&query),
+            vec![
+                (Field::Type, "library", false),
+                (Field::Framework, "angular", false),
+                (Field::Tag, "scope:payments", false),
+                (Field::Configuration, "prod", false),
+            ]
+        );
+        assert_eq!(query.text, "web:build");
+    }
+
+    #[test]
+    fn negation_and_phrases() {
+        let query = Query::parse(r#"-target:e2e "build prod" -storybook -"dev server" -"#).unwrap();
+
+        assert_eq!(
+            filters(&query),
+            vec![
+                (Field::Target, "e2e", true),
+                (Field::Text, "build prod", false),
+                (Field::Text, "storybook", true),
+                (Field::Text, "dev server", true),
+            ]
+        );
+        // A lone dash isn't a negation
+        assert_eq!(query.text, "-");
+    }
+
+    #[test]
+    fn quoted_filter_values_keep_their_spaces() {
+        let query = Query::parse(r#"tag:"team a""#).unwrap();
+        assert_eq!(filters(&query), vec![(Field::Tag, "team a", false)]);
+    }
+
+    #[test]
+    fn invalid_queries_are_rejected() {
+        assert_eq!(
+            Query::parse(r#"fw:"angular"#).unwrap_err(),
+            QueryError::UnclosedQuote
+        );
+        assert_eq!(
+            Query::parse("tag:").unwrap_err(),
+            QueryError::MissingValue("tag".to_string())
+        );
+        assert_eq!(
+            Query::parse("type:service").unwrap_err(),
+            QueryError::InvalidType("service".to_string())
+        );
+    }
+}
@@ -0,0 +1,389 @@
+use portable_pty::{native_pty_system, Child, CommandBuilder, MasterPty, PtySize};
+use ratatui::{prelude::*, widgets::*};
+use std::{
+    collections::VecDeque,
+    io::{Read, Write},
+    process::Command,
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+use tui_textarea::{Input, Key};
+
+use crate::history::{self, Run};
+
+// Lines kept above the screen for scrolling back through
+const SCROLLBACK: usize = 10_000;
+
+// A command for the runner, and the targets its run is recorded against
+pub struct Job {
+    pub command: Command,
+    pub targets: Vec<String>,
+    // Extra arguments the user added, as typed
+    pub args: String,
+}
+
+// What the user did with the pane after a key press
+pub enum RunnerAction {
+    Running,
+    Close,
+}
+
+// The job running right now, on its own pty
+struct Process {
+    child: Box<dyn Child + Send + Sync>,
+    master: Box<dyn MasterPty + Send>,
+    writer: Box<dyn Write + Send>,
+    targets: Vec<String>,
+    args: String,
+    started: Instant,
+}
+
+// How the last job ended, or why it couldn't be run
+enum Outcome {
+    Exited(u32),
+    Signalled(String),
+    Failed(String),
+}
+
+// Runs jobs one after the other on a pty, so nx still sees a terminal and keeps its colours,
+// stopping at the first that fails. Output from all of them goes through one terminal emulator
+// that the pane draws, keys typed while a job runs are passed on to it.
+pub struct Runner {
+    jobs: VecDeque<Job>,
+    process: Option<Process>,
+    parser: Arc<Mutex<vt100::Parser>>,
+    // Rows and columns of the emulated terminal, kept in step with the pane
+    size: (u16, u16),
+    started: Instant,
+    finished: Option<(Outcome, Duration)>,
+}
+
+impl Runner {
+    pub fn new(jobs: Vec<Job>) -> Runner {
+        // Near enough until the pane is first drawn and the real size is known
+        let (cols, rows) = crossterm::terminal::size().unwrap_or((80, 24));
+        let size = (rows.saturating_sub(2).max(1), cols.saturating_sub(2).max(1));
+
+        let mut runner = Runner {
+            jobs: jobs.into(),
+            process: None,
+            parser: Arc::new(Mutex::new(vt100::Parser::new(size.0, size.1, SCROLLBACK))),
+            size,
+            started: Instant::now(),
+            finished: None,
+        };
+        runner.start_next();
+        runner
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.finished.is_none()
+    }
+
+    fn start_next(&mut self) {
+        let Some(job) = self.jobs.pop_front() else {
+            return;
+        };
+
+        self.print(&format!(
+            "\x1b[1m> {}\x1b[0m\r\n",
+            command_line(&job.command)
+        ));
+        match self.spawn(job) {
+            Ok(process) => self.process = Some(process),
+            Err(e) => {
+                self.print(&format!("{}\r\n", e));
+                self.finished = Some((Outcome::Failed(e), self.started.elapsed()));
+            }
+        }
+    }
+
+    fn spawn(&self, job: Job) -> Result<Process, String> {
+        let pair = native_pty_system()
+            .openpty(pty_size(self.size))
+            .map_err(|e| format!("Could not open a pty: {}", e))?;
+
+        let mut builder = CommandBuilder::new(job.command.get_program());
+        builder.args(job.command.get_args());
+        // Without a directory the pty would start the command in the home directory
+        if let Some(dir) = job
+            .command
+            .get_current_dir()
+            .map(|dir| dir.to_path_buf())
+            .or_else(|| std::env::current_dir().ok())
+        {
+            builder.cwd(dir);
+        }
+
+        let child = pair
+            .slave
+            .spawn_command(builder)
+            .map_err(|e| format!("Could not run {}: {}", command_line(&job.command), e))?;
+        let reader = pair
+            .master
+            .try_clone_reader()
+            .map_err(|e| format!("Could not read from the pty: {}", e))?;
+        let writer = pair
+            .master
+            .take_writer()
+            .map_err(|e| format!("Could not write to the pty: {}", e))?;
+
+        let parser = Arc::clone(&self.parser);
+        thread::spawn(move || forward_output(reader, parser));
+
+        Ok(Process {
+            child,
+            master: pair.master,
+            writer,
+            targets: job.targets,
+            args: job.args,
+            started: Instant::now(),
+        })
+    }
+
+    fn print(&self, text: &str) {
+        if let Ok(mut parser) = self.parser.lock() {
+            parser.process(text.as_bytes());
+        }
+    }
+
+    // Checks on the running job, starting the next once it succeeds. Returns a run to record
+    // for each target of a job that's just finished.
+    pub fn poll(&mut self) -> Vec<Run> {
+        let Some(process) = &mut self.process else {
+            return vec![];
+        };
+        let status = match process.child.try_wait() {
+            Ok(None) => return vec![],
+            Ok(Some(status)) => Ok(status),
+            Err(e) => Err(format!("Lost track of the command: {}", e)),
+        };
+        let exit_code = status
+            .as_ref()
+            .ok()
+            .filter(|status| status.signal().is_none())
+            .map(|status| status.exit_code() as i32);
+
+        let runs = process
+            .targets
+            .iter()
+            .map(|target| Run {
+                target: target.clone(),
+                timestamp: history::now(),
+                exit_code,
+                duration_ms: process.started.elapsed().as_millis() as u64,
+                args: process.args.clone(),
+            })
+            .collect();
+
+        self.process = None;
+        if status.as_ref().is_ok_and(|status| status.success()) && !self.jobs.is_empty() {
+            self.print("\r\n");
+            self.start_next();
+        } else {
+            let outcome = match status {
+                Err(e) => Outcome::Failed(e),
+                Ok(status) => match status.signal() {
+                    Some(signal) => Outcome::Signalled(signal.to_string()),
+                    None => Outcome::Exited(status.exit_code()),
+                },
+            };
+            self.finished = Some((outcome, self.started.elapsed()));
+        }
+        runs
+    }
+
+    pub fn input(&mut self, input: Input) -> RunnerAction {
+        let page = (self.size.0 / 2).max(1) as isize;
+        match input {
+            Input { key: Key::Up, .. } => self.scroll(1),
+            Input { key: Key::Down, .. } => self.scroll(-1),
+            Input {
+                key: Key::PageUp, ..
+            } => self.scroll(page),
+            Input {
+                key: Key::PageDown, ..
+            } => self.scroll(-page),
+            Input {
+                key: Key::Esc | Key::Enter | Key::Char('q'),
+                ..
+            } if !self.is_running() => return RunnerAction::Close,
+            input => {
+                if let (Some(process), Some(bytes)) = (&mut self.process, key_bytes(&input)) {
+                    let _ = process.writer.write_all(&bytes);
+                    let _ = process.writer.flush();
+                }
+            }
+        }
+        RunnerAction::Running
+    }
+
+    // Moves `rows` further back into the scrollback (positive) or towards the live screen
+    fn scroll(&mut self, rows: isize) {
+        if let Ok(mut parser) = self.parser.lock() {
+            let current = parser.screen().scrollback();
+            parser.set_scrollback(current.saturating_add_signed(rows));
+        }
+    }
+
+    fn status(&self) -> Line<'static> {
+        let (text, color) = match &self.finished {
+            None => (
+                format!(
+                    " running for {}, ctrl + c to interrupt, up/down/pgup/pgdn to scroll ",
+                    duration_display(self.started.elapsed())
+                ),
+                Color::Yellow,
+            ),
+            Some((outcome, duration)) => {
+                let duration = duration_display(*duration);
+                match outcome {
+                    Outcome::Exited(0) => (format!(" done in {} ", duration), Color::Green),
+                    Outcome::Exited(code) => (
+                        format!(" exited with {} after {} ", code, duration),
+                        Color::Red,
+                    ),
+                    Outcome::Signalled(signal) => (
+                        format!(" stopped by {} after {} ", signal, duration),
+                        Color::Red,
+                    ),
+                    Outcome::Failed(e) => (format!(" {} ", e), Color::Red),
+                }
+            }
+        };
+
+        let mut spans = vec![Span::styled(text, Style::default().fg(color))];
+        if !self.is_running() {
+            spans.push(Span::styled(
+                "- esc/enter to go back to the list ",
+                Style::default().fg(Color::DarkGray),
+            ));
+        }
+        Line::from(spans)
+    }
+
+    pub fn render(&mut self, frame: &mut Frame, area: Rect) {
+        let block = Block::bordered()
+            .title("Output")
+            .title_bottom(self.status());
+        let inner = block.inner(area);
+        frame.render_widget(Clear, area);
+        frame.render_widget(block, area);
+
+        let size = (inner.height.max(1), inner.width.max(1));
+        let Ok(mut parser) = self.parser.lock() else {
+            return;
+        };
+        if size != self.size {
+            self.size = size;
+            parser.set_size(size.0, size.1);
+            if let Some(process) = &self.process {
+                let _ = process.master.resize(pty_size(size));
+            }
+        }
+
+        let screen = parser.screen();
+        let buffer = frame.buffer_mut();
+        for row in 0..inner.height {
+            for col in 0..inner.width {
+                let Some(cell) = screen.cell(row, col) else {
+                    continue;
+                };
+                if cell.is_wide_continuation() {
+                    continue;
+                }
+                if let Some(target) = buffer.cell_mut((inner.x + col, inner.y + row)) {
+                    let contents = cell.contents();
+                    target.set_symbol(if contents.is_empty() { " " } else { &contents });
+                    target.set_style(cell_style(cell));
+                }
+            }
+        }
+    }
+}
+
+// Feeds everything the job writes to the terminal emulator until the pty closes
+fn forward_output(mut reader: Box<dyn Read + Send>, parser: Arc<Mutex<vt100::Parser>>) {
+    let mut chunk = [0u8; 4096];
+    loop {
+        match reader.read(&mut chunk) {
+            Ok(0) | Err(_) => break,
+            Ok(n) => {
+                if let Ok(mut parser) = parser.lock() {
+                    parser.process(&chunk[..n]);
+                }
+            }
+        }
+    }
+}
+
+fn pty_size((rows, cols): (u16, u16)) -> PtySize {
+    PtySize {
+        rows,
+        cols,
+        pixel_width: 0,
+        pixel_height: 0,
+    }
+}
+
+fn command_line(command: &Command) -> String {
+    std::iter::once(command.get_program())
+        .chain(command.get_args())
+        .map(|word| word.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// What a terminal would send for the key, for the keys worth passing on
+fn key_bytes(input: &Input) -> Option<Vec<u8>> {
+    match input.key {
+        // ctrl + a..z are the control characters 1..26, ctrl + c among them
+        Key::Char(c) if input.ctrl && c.is_ascii_alphabetic() => {
+            Some(vec![c.to_ascii_lowercase() as u8 - b'a' + 1])
+        }
+        Key::Char(c) => Some(c.to_string().into_bytes()),
+        Key::Enter => Some(vec![b'\r']),
+        Key::Backspace => Some(vec![0x7f]),
+        Key::Tab => Some(vec![b'\t']),
+        Key::Esc => Some(vec![0x1b]),
+        _ => None,
+    }
+}
+
+fn cell_style(cell: &vt100::Cell) -> Style {
+    let mut style = Style::default()
+        .fg(color(cell.fgcolor()))
+        .bg(color(cell.bgcolor()));
+    if cell.bold() {
+        style = style.add_modifier(Modifier::BOLD);
+    }
+    if cell.italic() {
+        style = style.add_modifier(Modifier::ITALIC);
+    }
+    if cell.underline() {
+        style = style.add_modifier(Modifier::UNDERLINED);
+    }
+    if cell.inverse() {
+        style = style.add_modifier(Modifier::REVERSED);
+    }
+    style
+}
+
+fn color(color: vt100::Color) -> Color {
+    match color {
+        vt100::Color::Default => Color::Reset,
+        vt100::Color::Idx(i) => Color::Indexed(i),
+        vt100::Color::Rgb(r, g, b) => Color::Rgb(r, g, b),
+    }
+}
+
+// 850ms, 4.2s, 3m 12s
+fn duration_display(duration: Duration) -> String {
+    let millis = duration.as_millis();
+    match millis {
+        0..1000 => format!("{}ms", millis),
+        1000..60_000 => format!("{:.1}s", duration.as_secs_f64()),
+        _ => format!("{}m {}s", millis / 60_000, (millis % 60_000) / 1000),
+    }
+}
@@ -1,4 +1,13 @@
-use crate::detection::{project::ProjectType, Project};
+use crate::detection::{
+    affected::affected_projects,
+    executor_schema::executor_options,
+    nx_json::NxJson,
+    project::{Diagnostic, ProjectType, ScanEvent, Task},
+    Project,
+};
+use crate::favourites::Favourites;
+use crate::history::{self, History};
+use crate::state::State;
 use crossterm::{
     event::{self},
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
@@ -8,46 +17,81 @@ use fuzzy_matcher::skim::SkimMatcherV2;
 use fuzzy_matcher::FuzzyMatcher;
 use ratatui::{prelude::*, widgets::*};
 use std::{
+    collections::HashMap,
     io::{stdout, Result},
     path::{Path, PathBuf},
+    rc::Rc,
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
 };
 use tui_textarea::{Input, Key, TextArea};
 
+use super::args_prompt::{ArgsPrompt, PromptAction};
+use super::option_form::{FormAction, OptionForm};
+use super::query::{self, Field, Filter, Query};
+use super::runner::{Job, Runner, RunnerAction};
+use super::{details, diagnostics};
+
 const NAME: &str = env!("CARGO_PKG_NAME");
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 #[derive(Clone)]
 struct CommandEntry {
     project_type: ProjectType,
-    framework_name: Option<String>,
+    // Primary framework first
+    framework_names: Vec<String>,
     project_name: String,
+    tags: Vec<String>,
     command: String,
     subcommand: Option<String>,
+    // The target behind the command, None for framework commands the project doesn't define itself
+    task: Option<Rc<Task>>,
+    // Characters of display_string() the search matched, by char index
+    matched_indices: Vec<usize>,
 }
 
 impl CommandEntry {
-    fn display_string(&self) -> String {
+    fn type_display(&self) -> String {
         let project_type_str = match self.project_type {
             ProjectType::Library => "lib",
             ProjectType::Application => "app",
         };
 
-        let type_display = if let Some(framework) = &self.framework_name {
-            format!("{}:{}", project_type_str, framework)
-        } else {
+        if self.framework_names.is_empty() {
             project_type_str.to_string()
-        };
+        } else {
+            format!("{}:{}", project_type_str, self.framework_names.join(","))
+        }
+    }
 
-        if let Some(subcommand) = &self.subcommand {
+    fn display_string(&self) -> String {
+        let type_display = self.type_display();
+
+        // Tags go last so they can be searched without getting in the way of the name
+        let command = if let Some(subcommand) = &self.subcommand {
             format!(
                 "[{}] {}:{}:{}",
                 type_display, self.project_name, self.command, subcommand
             )
         } else {
             format!("[{}] {}:{}", type_display, self.project_name, self.command)
+        };
+        if self.tags.is_empty() {
+            command
+        } else {
+            format!("{} {}", command, self.tags.join(" "))
         }
     }
 
+    // Where the project name and the command start in display_string(), by char index
+    fn name_start(&self) -> usize {
+        self.type_display().chars().count() + "[] ".len()
+    }
+
+    fn command_start(&self) -> usize {
+        self.name_start() + self.project_name.chars().count() + ":".len()
+    }
+
     fn project_type_display(&self) -> String {
         match self.project_type {
             ProjectType::Library => "lib".to_string(),
@@ -63,6 +107,31 @@ impl CommandEntry {
         }
     }
 
+    fn executor(&self) -> Option<&str> {
+        self.task.as_ref()?.executor.as_deref()
+    }
+
+    fn matches(&self, filter: &Filter) -> bool {
+        let value = filter.value.as_str();
+        let is_match = match filter.field {
+            Field::Type => query::project_type(value) == Some(&self.project_type_display()),
+            // Framework names come with their version, the filter is only about the name
+            Field::Framework => self.framework_names.iter().any(|name| {
+                name.split_whitespace()
+                    .next()
+                    .is_some_and(|name| name.eq_ignore_ascii_case(value))
+            }),
+            Field::Tag => self.tags.iter().any(|tag| tag.eq_ignore_ascii_case(value)),
+            Field::Target => self.command.eq_ignore_ascii_case(value),
+            Field::Configuration => self
+                .subcommand
+                .as_ref()
+                .is_some_and(|subcommand| subcommand.eq_ignore_ascii_case(value)),
+            Field::Text => self.display_string().to_lowercase().contains(value),
+        };
+        is_match != filter.negated
+    }
+
     fn to_nx_command(&self) -> String {
         if let Some(subcommand) = &self.subcommand {
             format!("{}:{}:{}", self.project_name, self.command, subcommand)
@@ -72,56 +141,580 @@ impl CommandEntry {
     }
 }
 
+// One `nx run-many` for marked commands: every target on every project, in one configuration
+struct RunMany {
+    projects: Vec<String>,
+    targets: Vec<String>,
+    configuration: Option<String>,
+    // The nx commands it covers, to record in history
+    covers: Vec<String>,
+}
+
+impl RunMany {
+    // As few run-manys as cover the marked commands. Targets marked on exactly the same projects
+    // share one, so nx can run them side by side.
+    fn group(marked: &[CommandEntry]) -> Vec<RunMany> {
+        // First the projects each target and configuration was marked on...
+        let mut runs: Vec<RunMany> = Vec::new();
+        for entry in marked {
+            match runs.iter_mut().find(|run| {
+                run.targets[0] == entry.command && run.configuration == entry.subcommand
+            }) {
+                Some(run) => {
+                    run.projects.push(entry.project_name.clone());
+                    run.covers.push(entry.to_nx_command());
+                }
+                None => runs.push(RunMany {
+                    projects: vec![entry.project_name.clone()],
+                    targets: vec![entry.command.clone()],
+                    configuration: entry.subcommand.clone(),
+                    covers: vec![entry.to_nx_command()],
+                }),
+            }
+        }
+
+        // ...then targets with the same projects folded together
+        let mut merged: Vec<RunMany> = Vec::new();
+        for run in runs {
+            let same_projects = |other: &RunMany| {
+                other.configuration == run.configuration
+                    && other.projects.len() == run.projects.len()
+                    && run.projects.iter().all(|p| other.projects.contains(p))
+            };
+            match merged.iter_mut().find(|other| same_projects(other)) {
+                Some(other) => {
+                    other.targets.extend(run.targets);
+                    other.covers.extend(run.covers);
+                }
+                None => merged.push(run),
+            }
+        }
+        merged
+    }
+}
+
 struct App {
     search_path: PathBuf,
     projects: Vec<Project>,
     all_commands: Vec<CommandEntry>,
     display_commands: Vec<CommandEntry>,
+    // How many of display_commands are pinned ones, drawn in their own section above the rest
+    pinned: usize,
     selection: ListState,
+    search: String,
+    scan: Option<Receiver<ScanEvent>>,
+    scanned: Vec<Project>,
+    scan_progress: Option<(usize, usize)>,
+    diagnostics: Vec<Diagnostic>,
+    show_diagnostics: bool,
+    show_details: bool,
+    // Why the search box couldn't be parsed, the list keeps its last good filter meanwhile
+    query_error: Option<String>,
+    history: History,
+    // Frecency of each nx command, used to rank the list. Worked out again after every run.
+    frecency: HashMap<String, u64>,
+    favourites: Favourites,
+    // nx commands marked to launch together, in the order they were marked
+    marked: Vec<String>,
+    // Set by moving around the list, so space marks rows instead of going into the search box
+    list_focused: bool,
+    // Git ref affected mode compares against
+    base_ref: String,
+    show_affected: bool,
+    // Names of the affected projects, worked out again each time affected mode is turned on,
+    // after a run and on ctrl + g. The last answer stays in use while a new one is worked out.
+    affected: Option<Vec<String>>,
+    affected_scan: Option<Receiver<std::result::Result<Vec<String>, String>>>,
+    affected_error: Option<String>,
+    // A command picked in affected mode, waiting on whether to run it alone or for all affected
+    offer: Option<CommandEntry>,
+    // The command having arguments added before it's launched
+    args_prompt: Option<(CommandEntry, ArgsPrompt)>,
+    // The command having its executor options edited before it's launched
+    option_form: Option<(CommandEntry, OptionForm)>,
+    // Why the last action couldn't be done, cleared by the next key press
+    notice: Option<String>,
+    // The output pane, from launching a command until it's closed
+    runner: Option<Runner>,
 }
 
 impl App {
     fn new(search_path: &Path) -> App {
         let mut selection = ListState::default();
         selection.select(Some(0));
+        let history = History::load(search_path);
+        let base_ref = State::global()
+            .lock()
+            .base
+            .clone()
+            .or_else(|| NxJson::read(search_path).ok()?.default_base)
+            .unwrap_or_else(|| "main".to_string());
 
         App {
             search_path: search_path.to_path_buf(),
             projects: vec![],
             all_commands: vec![],
             display_commands: vec![],
+            pinned: 0,
             selection,
+            search: String::new(),
+            scan: None,
+            scanned: vec![],
+            scan_progress: None,
+            diagnostics: vec![],
+            show_diagnostics: false,
+            show_details: false,
+            query_error: None,
+            frecency: history.frecency(history::now()),
+            history,
+            favourites: Favourites::load(search_path),
+            marked: vec![],
+            list_focused: false,
+            base_ref,
+            show_affected: false,
+            affected: None,
+            affected_scan: None,
+            affected_error: None,
+            offer: None,
+            args_prompt: None,
+            option_form: None,
+            notice: None,
+            runner: None,
         }
     }
 
+    // Shows the projects from the last run straight away and streams in a fresh scan from a
+    // worker thread, so the list and search box are usable while it runs
     fn detect_projects(&mut self) {
-        self.projects = Project::detect(self.search_path.as_path());
+        self.set_projects(Project::detect_cached(self.search_path.as_path()));
+
+        let (tx, rx) = mpsc::channel();
+        let search_path = self.search_path.clone();
+        thread::spawn(move || Project::detect(search_path.as_path(), &tx));
+        self.scan = Some(rx);
     }
 
-    fn select(&self) -> bool {
-        if let Some(i) = self.selection.selected() {
-            if i < self.display_commands.len() {
-                let selected_command = &self.display_commands[i];
-                let _ = cleanup();
+    fn is_scanning(&self) -> bool {
+        self.scan.is_some()
+    }
 
-                let status = std::process::Command::new("nx")
-                    .arg("run")
-                    .arg(selected_command.to_nx_command())
-                    .status()
-                    .expect("Failed to execute nx command");
+    fn scan_status(&self) -> String {
+        match (self.is_scanning(), self.scan_progress) {
+            (false, _) => String::new(),
+            (true, Some((done, total))) => format!(" (scanning {}/{})", done, total),
+            (true, None) => " (scanning...)".to_string(),
+        }
+    }
 
-                if status.success() {
-                    std::process::exit(0);
+    // Drains whatever the scanner has sent since the last frame, rebuilding the list once
+    fn poll_scan(&mut self) {
+        let Some(scan) = self.scan.take() else {
+            return;
+        };
+
+        // Only copied once something actually arrives, most frames have nothing new
+        let mut finished = false;
+        let mut projects: Option<Vec<Project>> = None;
+        loop {
+            match scan.try_recv() {
+                Ok(ScanEvent::Started { total }) => self.scan_progress = Some((0, total)),
+                Ok(ScanEvent::Scanned(project)) => {
+                    if let Some((done, _)) = &mut self.scan_progress {
+                        *done += 1;
+                    }
+                    let Some(project) = project else {
+                        continue;
+                    };
+
+                    // Freshly parsed projects replace their cached counterparts as they come in
+                    let projects = projects.get_or_insert_with(|| self.projects.clone());
+                    match projects.iter_mut().find(|p| p.name == project.name) {
+                        Some(existing) => *existing = project.clone(),
+                        None => projects.push(project.clone()),
+                    }
+                    self.scanned.push(project);
+                }
+                Ok(ScanEvent::Diagnostic(diagnostic)) => self.add_diagnostic(diagnostic),
+                Ok(ScanEvent::Replaced(graph_projects)) => {
+                    projects = Some(graph_projects.clone());
+                    self.scanned = graph_projects;
                 }
+                Ok(ScanEvent::Finished) | Err(TryRecvError::Disconnected) => {
+                    // Anything cached that the scan didn't find again is gone now
+                    projects = Some(std::mem::take(&mut self.scanned));
+                    finished = true;
+                    self.scan_progress = None;
+                    break;
+                }
+                Err(TryRecvError::Empty) => break,
+            }
+        }
+
+        if !finished {
+            self.scan = Some(scan);
+        }
+
+        if let Some(projects) = projects {
+            self.set_projects(projects);
+        }
+    }
+
+    // The graph and the file scan can trip over the same file, only keep the first report
+    fn add_diagnostic(&mut self, diagnostic: Diagnostic) {
+        let is_duplicate = self.diagnostics.iter().any(|d| {
+            d.path == diagnostic.path && d.error.to_string() == diagnostic.error.to_string()
+        });
+        if !is_duplicate {
+            self.diagnostics.push(diagnostic);
+        }
+    }
+
+    fn toggle_diagnostics(&mut self) {
+        self.show_diagnostics = !self.show_diagnostics;
+    }
+
+    fn toggle_details(&mut self) {
+        self.show_details = !self.show_details;
+    }
+
+    // Switches between every project and just the affected ones, which are worked out afresh
+    // in the background in case the branch has moved on
+    fn toggle_affected(&mut self) {
+        self.show_affected = !self.show_affected;
+        self.affected_error = None;
+
+        if self.show_affected {
+            self.refresh_affected();
+        }
+        self.filter_commands(&self.search.clone());
+    }
+
+    fn refresh_affected(&mut self) {
+        if self.affected_scan.is_some() {
+            return;
+        }
+
+        let (tx, rx) = mpsc::channel();
+        let search_path = self.search_path.clone();
+        let base_ref = self.base_ref.clone();
+        let roots: Vec<(String, String)> = self
+            .projects
+            .iter()
+            .map(|p| (p.name.clone(), p.root.clone()))
+            .collect();
+        thread::spawn(move || {
+            let _ = tx.send(affected_projects(&search_path, &base_ref, &roots));
+        });
+        self.affected_scan = Some(rx);
+    }
+
+    fn poll_affected(&mut self) {
+        let Some(scan) = &self.affected_scan else {
+            return;
+        };
+
+        match scan.try_recv() {
+            Ok(Ok(affected)) => self.affected = Some(affected),
+            Ok(Err(e)) => {
+                self.affected_error = Some(e);
+                self.show_affected = false;
+            }
+            Err(TryRecvError::Empty) => return,
+            Err(TryRecvError::Disconnected) => self.show_affected = false,
+        }
+        self.affected_scan = None;
+        self.filter_commands(&self.search.clone());
+    }
+
+    fn affected_status(&self) -> String {
+        match (&self.affected_error, self.show_affected, &self.affected) {
+            (Some(e), _, _) => format!(" - affected unavailable: {}", e),
+            (None, false, _) => String::new(),
+            (None, true, Some(_)) if self.affected_scan.is_some() => {
+                format!(" - affected since {}, refreshing...", self.base_ref)
+            }
+            (None, true, Some(_)) => format!(" - affected since {}", self.base_ref),
+            (None, true, None) => format!(" - finding affected since {}...", self.base_ref),
+        }
+    }
+
+    // Affected mode is on but there's no answer yet to filter the list with
+    fn affected_pending(&self) -> bool {
+        self.show_affected && self.affected.is_none()
+    }
+
+    fn is_affected(&self, cmd: &CommandEntry) -> bool {
+        !self.show_affected
+            || self
+                .affected
+                .as_ref()
+                .is_some_and(|affected| affected.contains(&cmd.project_name))
+    }
+
+    fn selected_command(&self) -> Option<&CommandEntry> {
+        self.selection
+            .selected()
+            .and_then(|i| self.display_commands.get(i))
+    }
+
+    // Swaps in a new project list, keeping the current search and selected command if it's still there
+    fn set_projects(&mut self, projects: Vec<Project>) {
+        let selected = self
+            .selection
+            .selected()
+            .and_then(|i| self.display_commands.get(i))
+            .map(|cmd| cmd.to_nx_command());
+
+        self.projects = projects;
+        self.all_commands = construct(&self.projects);
+        self.filter_commands(&self.search.clone());
+
+        if let Some(selected) = selected {
+            self.select_command(&selected);
+        }
+    }
+
+    // Moves the selection to the given nx command if it's in the list
+    fn select_command(&mut self, nx_command: &str) {
+        if let Some(i) = self
+            .display_commands
+            .iter()
+            .position(|cmd| cmd.to_nx_command() == nx_command)
+        {
+            self.selection.select(Some(i));
+        }
+    }
+
+    // Pins or unpins the selected command, which then moves in or out of the pinned section
+    fn toggle_favourite(&mut self) {
+        let Some(selected) = self.selected_command().map(|cmd| cmd.to_nx_command()) else {
+            return;
+        };
+
+        self.favourites.toggle(&self.search_path, &selected);
+        self.filter_commands(&self.search.clone());
+        self.select_command(&selected);
+    }
+
+    fn toggle_mark(&mut self) {
+        let Some(selected) = self.selected_command().map(|cmd| cmd.to_nx_command()) else {
+            return;
+        };
+
+        match self.marked.iter().position(|m| *m == selected) {
+            Some(i) => {
+                self.marked.remove(i);
+            }
+            None => self.marked.push(selected),
+        }
+        self.next();
+    }
+
+    fn is_marked(&self, cmd: &CommandEntry) -> bool {
+        self.marked.contains(&cmd.to_nx_command())
+    }
+
+    // Space marks rows while moving around the list, or when there's nothing to type a space after
+    fn space_marks(&self) -> bool {
+        self.list_focused || self.search.is_empty()
+    }
+
+    fn select(&mut self) {
+        if !self.marked.is_empty() {
+            return self.launch_marked();
+        }
+
+        let Some(selected) = self.selected_command().cloned() else {
+            return;
+        };
+
+        // In affected mode the target might be wanted for every affected project, so ask first
+        if self.show_affected {
+            self.offer = Some(selected);
+            return;
+        }
+        self.launch_one(&selected)
+    }
+
+    fn launch_one(&mut self, cmd: &CommandEntry) {
+        let target = cmd.to_nx_command();
+        let mut command = std::process::Command::new("nx");
+        command.arg("run").arg(&target);
+        self.launch(command, &[target], "")
+    }
+
+    // Opens the argument prompt on the selected command
+    fn prompt_args(&mut self) {
+        let Some(selected) = self.selected_command().cloned() else {
+            return;
+        };
+
+        let target = selected.to_nx_command();
+        let prompt = ArgsPrompt::new(
+            &format!("nx run {}", target),
+            self.history.previous_args(&target),
+        );
+        self.args_prompt = Some((selected, prompt));
+    }
+
+    // `nx run <target>` with the arguments from the prompt
+    fn launch_with_args(&mut self, cmd: &CommandEntry, words: &[String], args: &str) {
+        let target = cmd.to_nx_command();
+        let mut command = std::process::Command::new("nx");
+        command.arg("run").arg(&target).args(words);
+        self.launch(command, &[target], args)
+    }
+
+    // Opens the option form on the selected command, when its executor's schema can be found
+    fn open_option_form(&mut self) {
+        let Some(selected) = self.selected_command().cloned() else {
+            return;
+        };
+        let target = selected.to_nx_command();
+        let Some(task) = selected.task.clone() else {
+            self.notice = Some(format!("{} isn't defined by the project", target));
+            return;
+        };
+        let Some(executor) = task.executor.as_deref() else {
+            self.notice = Some(format!("{} has no executor", target));
+            return;
+        };
+
+        let project_path = self
+            .projects
+            .iter()
+            .find(|project| project.name == selected.project_name)
+            .map(|project| self.search_path.join(&project.root))
+            .unwrap_or_else(|| self.search_path.clone());
+        let options = match executor_options(&self.search_path, &project_path, executor) {
+            Ok(options) => options,
+            Err(e) => {
+                self.notice = Some(e);
+                return;
+            }
+        };
+
+        let form = OptionForm::new(
+            &target,
+            options,
+            &task.options_for(selected.subcommand.as_deref()),
+        );
+        if form.is_empty() {
+            self.notice = Some(format!("{} has no options to edit", executor));
+            return;
+        }
+        self.option_form = Some((selected, form));
+    }
+
+    // `nx run <target>` with the options changed in the form
+    fn launch_with_overrides(&mut self, cmd: &CommandEntry, overrides: &[String]) {
+        let target = cmd.to_nx_command();
+        let mut command = std::process::Command::new("nx");
+        command.arg("run").arg(&target).args(overrides);
+        self.launch(command, &[target], &overrides.join(" "))
+    }
+
+    // `nx affected -t <target>` against the same base the list is showing
+    fn launch_affected(&mut self, cmd: &CommandEntry) {
+        let mut command = std::process::Command::new("nx");
+        command
+            .arg("affected")
+            .arg("-t")
+            .arg(&cmd.command)
+            .arg(format!("--base={}", self.base_ref));
+        if let Some(configuration) = &cmd.subcommand {
+            command.arg(format!("--configuration={}", configuration));
+        }
+        if let Some(parallel) = State::global().lock().parallel.clone() {
+            command.arg(format!("--parallel={}", parallel));
+        }
+        self.launch(command, &[cmd.to_nx_command()], "")
+    }
+
+    // Runs the command in the output pane, recording it against each of `targets` once it's done
+    fn launch(&mut self, command: std::process::Command, targets: &[String], args: &str) {
+        self.runner = Some(Runner::new(vec![Job {
+            command,
+            targets: targets.to_vec(),
+            args: args.to_string(),
+        }]));
+    }
+
+    // Each target gets its own history entry so they all count towards frecency
+    fn poll_runner(&mut self) {
+        let Some(runner) = &mut self.runner else {
+            return;
+        };
+        let runs = runner.poll();
+        if runs.is_empty() {
+            return;
+        }
+        for run in runs {
+            self.history.record(&self.search_path, run);
+        }
+
+        // Re-rank with the new runs counted, keeping the selection on the same command
+        self.frecency = self.history.frecency(history::now());
+        let selected = self.selected_command().map(|cmd| cmd.to_nx_command());
+        self.filter_commands(&self.search.clone());
+        if let Some(selected) = selected {
+            self.select_command(&selected);
+        }
+
+        // Whatever ran may have changed files, generators especially
+        if self.show_affected {
+            self.refresh_affected();
+        }
+    }
+
+    // Runs every marked command with `nx run-many`, one after the other, stopping at the first
+    // that fails
+    fn launch_marked(&mut self) {
+        let entries: Vec<CommandEntry> = self
+            .marked
+            .iter()
+            .filter_map(|marked| {
+                self.all_commands
+                    .iter()
+                    .find(|cmd| cmd.to_nx_command() == *marked)
+                    .cloned()
+            })
+            .collect();
+        if entries.is_empty() {
+            return;
+        }
 
-                println!("Command exited with status: {}", status);
-                return true;
+        let parallel = State::global().lock().parallel.clone();
+        let mut jobs = Vec::new();
+
+        for run in RunMany::group(&entries) {
+            let mut command = std::process::Command::new("nx");
+            command
+                .arg("run-many")
+                .arg(format!("--projects={}", run.projects.join(",")))
+                .arg(format!("--targets={}", run.targets.join(",")));
+            if let Some(configuration) = &run.configuration {
+                command.arg(format!("--configuration={}", configuration));
+            }
+            if let Some(parallel) = &parallel {
+                command.arg(format!("--parallel={}", parallel));
             }
+
+            jobs.push(Job {
+                command,
+                targets: run.covers,
+                args: String::new(),
+            });
         }
-        false
+
+        self.marked.clear();
+        self.runner = Some(Runner::new(jobs));
     }
 
     fn next(&mut self) {
+        if self.display_commands.is_empty() {
+            return;
+        }
         let i = match self.selection.selected() {
             Some(i) => {
                 if i >= self.display_commands.len() - 1 {
@@ -136,6 +729,9 @@ impl App {
     }
 
     fn previous(&mut self) {
+        if self.display_commands.is_empty() {
+            return;
+        }
         let i = match self.selection.selected() {
             Some(i) => {
                 if i == 0 {
@@ -150,38 +746,141 @@ impl App {
     }
 
     fn prep_for_matching(input: &str) -> String {
-        input
-            .replace("[", "")
-            .replace("]", "")
-            .replace(":", "")
-            .replace("-", "")
-            .replace(" ", "")
-            .to_lowercase()
+        Self::prep_with_positions(input).0
+    }
+
+    // prep_for_matching, along with the char index in `input` each prepped char came from
+    fn prep_with_positions(input: &str) -> (String, Vec<usize>) {
+        let mut prepped = String::new();
+        let mut positions = Vec::new();
+        for (i, c) in input.chars().enumerate() {
+            if matches!(c, '[' | ']' | ':' | '-' | ' ') {
+                continue;
+            }
+            for lower in c.to_lowercase() {
+                prepped.push(lower);
+                positions.push(i);
+            }
+        }
+        (prepped, positions)
+    }
+
+    // The string the fuzzy matcher sees for a row: the prepped display string followed by its
+    // words in reverse order. Positions map each char back to display_string(), None for the
+    // spaces added in between.
+    fn match_candidate(display: &str) -> (String, Vec<Option<usize>>) {
+        let (mut candidate, positions) = Self::prep_with_positions(display);
+        let mut positions: Vec<Option<usize>> = positions.into_iter().map(Some).collect();
+
+        let chars: Vec<char> = display.chars().collect();
+        let mut words = Vec::new();
+        let mut word_start = None;
+        for (i, c) in chars.iter().enumerate() {
+            match (c.is_whitespace(), word_start) {
+                (true, Some(start)) => {
+                    words.push(start..i);
+                    word_start = None;
+                }
+                (false, None) => word_start = Some(i),
+                _ => (),
+            }
+        }
+        if let Some(start) = word_start {
+            words.push(start..chars.len());
+        }
+
+        for word in words.into_iter().rev() {
+            candidate.push(' ');
+            positions.push(None);
+            for i in word {
+                candidate.push(chars[i]);
+                positions.push(Some(i));
+            }
+        }
+        (candidate, positions)
+    }
+
+    // The score of a row against the prepped search text, and which chars of display_string()
+    // matched
+    fn fuzzy_match(
+        matcher: &SkimMatcherV2,
+        cmd: &CommandEntry,
+        prepped_search: &str,
+    ) -> Option<(i64, Vec<usize>)> {
+        let (candidate, positions) = Self::match_candidate(&cmd.display_string());
+        let (score, indices) = matcher.fuzzy_indices(&candidate, prepped_search)?;
+
+        let mut matched_indices: Vec<usize> =
+            indices.iter().filter_map(|&i| positions[i]).collect();
+        matched_indices.sort();
+        matched_indices.dedup();
+        Some((score, matched_indices))
     }
 
     fn filter_commands(&mut self, search: &str) {
+        self.search = search.to_string();
+        let query = match Query::parse(search) {
+            Ok(query) => query,
+            Err(e) => {
+                self.query_error = Some(e.to_string());
+                return;
+            }
+        };
+        self.query_error = None;
+
         let matcher = SkimMatcherV2::default();
-        let prepped_search = Self::prep_for_matching(search);
+        let prepped_search = Self::prep_for_matching(&query.text);
 
         let mut matched_commands: Vec<(i64, CommandEntry)> = self
             .all_commands
             .iter()
+            .filter(|cmd| self.is_affected(cmd))
+            .filter(|cmd| query.filters.iter().all(|filter| cmd.matches(filter)))
             .filter_map(|cmd| {
-                let display = cmd.display_string();
-                let mut prepped_cmd = Self::prep_for_matching(&display);
-                let reversed_words_command =
-                    display.split_whitespace().rev().collect::<Vec<&str>>();
-                prepped_cmd = prepped_cmd + " " + &reversed_words_command.join(" ");
-
-                matcher
-                    .fuzzy_match(&prepped_cmd, &prepped_search)
-                    .map(|score| (score, cmd.clone()))
+                let (score, matched_indices) = Self::fuzzy_match(&matcher, cmd, &prepped_search)?;
+                Some((
+                    score,
+                    CommandEntry {
+                        matched_indices,
+                        ..cmd.clone()
+                    },
+                ))
             })
             .collect();
 
-        matched_commands.sort_by(|a, b| b.0.cmp(&a.0));
+        // With nothing typed every score is the same, so frecency decides the order outright
+        matched_commands.sort_by_key(|(score, cmd)| {
+            let frecency = self.frecency.get(&cmd.to_nx_command()).copied();
+            (
+                std::cmp::Reverse(*score),
+                std::cmp::Reverse(frecency.unwrap_or_default()),
+            )
+        });
 
-        self.display_commands = matched_commands.into_iter().map(|(_, cmd)| cmd).collect();
+        // Pinned commands head the list in the order they were pinned, whether or not they match
+        // the search. Affected mode still applies to them: it's there to show what a change
+        // touches, and an unaffected pin would suggest otherwise.
+        let mut matched_commands: Vec<CommandEntry> =
+            matched_commands.into_iter().map(|(_, cmd)| cmd).collect();
+        let mut pinned = Vec::new();
+        for favourite in self.favourites.iter() {
+            if let Some(i) = matched_commands
+                .iter()
+                .position(|cmd| &cmd.to_nx_command() == favourite)
+            {
+                pinned.push(matched_commands.remove(i));
+            } else if let Some(cmd) = self
+                .all_commands
+                .iter()
+                .filter(|cmd| self.is_affected(cmd))
+                .find(|cmd| &cmd.to_nx_command() == favourite)
+            {
+                pinned.push(cmd.clone());
+            }
+        }
+        self.pinned = pinned.len();
+        pinned.extend(matched_commands);
+        self.display_commands = pinned;
 
         if self.display_commands.is_empty() {
             self.selection.select(None);
@@ -217,39 +916,41 @@ pub fn run_app(search_path: String) -> Result<()> {
     let mut app = App::new(Path::new(&search_path));
     let mut terminal = Terminal::new(CrosstermBackend::new(stdout())).unwrap();
     let mut textarea = TextArea::default();
+    textarea
+        .set_placeholder_text("search, or filter with type: fw: tag: target: cfg: and -exclusions");
     terminal.clear()?;
-    terminal.draw(|f| {
-        let size = f.area();
-        let block = Block::default().title("Scanning...").borders(Borders::ALL);
-        f.render_widget(block, size);
-    })?;
 
     app.detect_projects();
-    app.display_commands = construct(&app.projects);
-    app.all_commands = app.display_commands.clone();
-    terminal.clear()?;
-    if app.display_commands.is_empty() {
-        println!("No projects found in the specified path");
-        return Ok(());
-    }
+    let mut nothing_to_run = false;
     loop {
+        app.poll_scan();
+        app.poll_affected();
+        app.poll_runner();
+        if !app.is_scanning() && app.all_commands.is_empty() {
+            // Nothing to run, but if something failed to parse that's worth showing instead
+            if app.diagnostics.is_empty() {
+                terminal.clear()?;
+                println!("No projects found in the specified path");
+                return Ok(());
+            }
+            // Only opened on the way in, so ctrl + d can still close it
+            if !nothing_to_run {
+                app.show_diagnostics = true;
+                nothing_to_run = true;
+            }
+        } else {
+            nothing_to_run = false;
+        }
+
         terminal.draw(|frame| {
             let area = frame.area();
-            let layout = Layout::default()
-                .direction(Direction::Vertical)
-                .constraints([
-                    Constraint::Percentage(8),
-                    Constraint::Percentage(87),
-                    Constraint::Percentage(5),
-                ])
-                .split(area);
 
             // Combined title block
             let title_block = Block::default()
                 .title(Line::from(vec![])) // Add top padding line
                 .title_alignment(Alignment::Center);
 
-            let titles = Paragraph::new(vec![
+            let mut title_lines = vec![
                 Line::from(vec![Span::styled(
                     format!("{} {}", NAME, VERSION),
                     Style::default()
@@ -258,136 +959,354 @@ pub fn run_app(search_path: String) -> Result<()> {
                 )])
                 .alignment(Alignment::Center),
                 Line::from(vec![]),
-                Line::from(vec![Span::styled(
-                    format!(
-                        "{} projects with {} tasks",
-                        app.projects.len(),
-                        app.display_commands.len()
+                Line::from(vec![
+                    Span::styled(
+                        format!(
+                            "{} projects with {} tasks",
+                            app.projects.len(),
+                            app.display_commands.len()
+                        ),
+                        Style::default().fg(Color::Gray),
                     ),
-                    Style::default().fg(Color::Gray),
-                )])
+                    Span::styled(app.scan_status(), Style::default().fg(Color::DarkGray)),
+                    Span::styled(
+                        match app.marked.len() {
+                            0 => String::new(),
+                            n => format!(" - {} marked", n),
+                        },
+                        Style::default().fg(Color::LightCyan),
+                    ),
+                    Span::styled(
+                        app.affected_status(),
+                        Style::default().fg(Color::LightMagenta),
+                    ),
+                    Span::styled(
+                        match &app.notice {
+                            Some(notice) => format!(" - {}", notice),
+                            None => String::new(),
+                        },
+                        Style::default().fg(Color::Red),
+                    ),
+                    Span::styled(
+                        match app.diagnostics.len() {
+                            0 => String::new(),
+                            n => format!(" - {} diagnostics", n),
+                        },
+                        Style::default().fg(Color::Red),
+                    ),
+                ])
                 .alignment(Alignment::Center),
-                Line::from(vec![Span::styled(
+            ];
+            // Grouped over a few lines so they all fit on an 80 column terminal
+            title_lines.extend(
+                [
                     "arrow keys to navigate, enter/tab to select, q / esc to quit",
-                    Style::default().fg(Color::Yellow),
-                )])
-                .alignment(Alignment::Center),
-                Line::from(vec![Span::styled(
-                    "ctrl + r to reset nx",
-                    Style::default().fg(Color::Yellow),
-                )])
-                .alignment(Alignment::Center),
-            ]);
+                    "ctrl + p to pin, ctrl + e to add arguments, ctrl + o to edit options",
+                    "ctrl + a for affected, ctrl + g to refresh it, ctrl + t for target details",
+                    "space to mark, ctrl + d for diagnostics, ctrl + r to reset nx",
+                ]
+                .map(|keys| {
+                    Line::from(Span::styled(keys, Style::default().fg(Color::Yellow)))
+                        .alignment(Alignment::Center)
+                }),
+            );
+
+            // The header is as tall as its lines, the list gets the rest
+            let layout = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([
+                    Constraint::Length(title_lines.len() as u16),
+                    Constraint::Min(0),
+                    Constraint::Percentage(5),
+                ])
+                .split(area);
+            let titles = Paragraph::new(title_lines);
 
             frame.render_widget(title_block, layout[0]);
             frame.render_widget(titles, layout[0]);
 
-            // List in center column
-            let list_area = Layout::default()
+            // List in center column, shifted left to make room when the details are shown
+            let columns = Layout::default()
                 .direction(Direction::Horizontal)
-                .constraints([
-                    Constraint::Percentage(20),
-                    Constraint::Percentage(60),
-                    Constraint::Percentage(20),
-                ])
-                .split(layout[1])[1];
+                .constraints(column_constraints(app.show_details))
+                .split(layout[1]);
+            let mut list_area = columns[1];
+
+            if app.show_details {
+                if let Some(cmd) = app.selected_command() {
+                    let project = app.projects.iter().find(|p| p.name == cmd.project_name);
+                    details::render(
+                        frame,
+                        columns[2],
+                        &cmd.to_nx_command(),
+                        project,
+                        cmd.task.as_deref(),
+                        cmd.subcommand.as_deref(),
+                    );
+                }
+            }
+
+            // Diagnostics take the bottom part of the list column when shown
+            if app.show_diagnostics {
+                let split = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+                    .split(list_area);
+                list_area = split[0];
+                diagnostics::render(frame, split[1], &app.diagnostics, &app.search_path);
+            }
 
-            let items: Vec<ListItem> = app
+            let mut items: Vec<ListItem> = app
                 .display_commands
                 .iter()
                 .map(|cmd| {
-                    ListItem::new(Line::from(vec![
+                    let mark = if app.is_marked(cmd) { "● " } else { "  " };
+                    let mut spans = vec![
+                        Span::styled(mark, Style::default().fg(Color::LightCyan)),
                         Span::styled(
-                            format!("{:20}", cmd.project_type_display()),
+                            format!("{:6}", cmd.project_type_display()),
                             Style::default().fg(Color::LightRed),
                         ),
+                    ];
+                    spans.extend(framework_badges(&cmd.framework_names, 32));
+                    spans.extend(highlighted(
+                        &cmd.project_name,
+                        33,
+                        cmd.name_start(),
+                        &cmd.matched_indices,
+                        Style::default().fg(Color::LightGreen),
+                    ));
+                    spans.extend(highlighted(
+                        &cmd.command_display(),
+                        24,
+                        cmd.command_start(),
+                        &cmd.matched_indices,
+                        Style::default().fg(Color::LightYellow),
+                    ));
+                    spans.extend([
                         Span::styled(
-                            format!(
-                                "{:20}",
-                                cmd.framework_name.clone().unwrap_or("".to_string())
-                            ),
-                            Style::default().fg(Color::LightBlue),
-                        ),
-                        Span::styled(
-                            format!("{:40}", cmd.project_name),
-                            Style::default().fg(Color::LightGreen),
+                            format!("{:32} ", cmd.tags.join(" ")),
+                            Style::default().fg(Color::Magenta),
                         ),
                         Span::styled(
-                            cmd.command_display(),
-                            Style::default().fg(Color::LightYellow),
+                            cmd.executor().unwrap_or_default().to_string(),
+                            Style::default().fg(Color::DarkGray),
                         ),
-                    ]))
+                    ]);
+                    ListItem::new(Line::from(spans))
                 })
                 .collect();
 
-            // Making the selection highlight more visible with a darker background
-            let list = List::new(items)
-                .block(Block::bordered().title("Projects"))
-                .highlight_style(
-                    Style::default()
-                        .bg(Color::DarkGray)
-                        .fg(Color::White) // Makes the text really pop when selected
-                        .add_modifier(Modifier::BOLD),
+            // Pinned commands get their own block above the rest, taking at most half the column.
+            // The selection runs through both, so each block gets its part of it.
+            let selected = app.selection.selected();
+            let rest = items.split_off(app.pinned);
+            if app.pinned > 0 {
+                let height = (app.pinned as u16).min(list_area.height / 2) + 2;
+                let [pinned_area, rest_area] =
+                    Layout::vertical([Constraint::Length(height), Constraint::Min(0)])
+                        .areas(list_area);
+                list_area = rest_area;
+
+                let mut pinned_selection =
+                    ListState::default().with_selected(selected.filter(|&i| i < app.pinned));
+                frame.render_stateful_widget(
+                    command_list(items, "Pinned"),
+                    pinned_area,
+                    &mut pinned_selection,
                 );
-            frame.render_stateful_widget(list, list_area, &mut app.selection);
+            }
+
+            // An empty list would read as nothing being affected, so say it's still being worked out
+            if app.affected_pending() {
+                let pending = Paragraph::new(Span::styled(
+                    format!("Finding the projects affected since {}...", app.base_ref),
+                    Style::default().fg(Color::DarkGray),
+                ))
+                .block(Block::bordered().title("Projects"));
+                frame.render_widget(pending, list_area);
+            } else {
+                let mut rest_selection = ListState::default()
+                    .with_offset(app.selection.offset())
+                    .with_selected(selected.and_then(|i| i.checked_sub(app.pinned)));
+                frame.render_stateful_widget(
+                    command_list(rest, "Projects"),
+                    list_area,
+                    &mut rest_selection,
+                );
+                *app.selection.offset_mut() = rest_selection.offset();
+            }
 
             // For the list and textbox, we'll split vertically with percentage constraints
 
             let search_area = Layout::default()
                 .direction(Direction::Horizontal)
-                .constraints([
-                    Constraint::Percentage(20),
-                    Constraint::Percentage(60),
-                    Constraint::Percentage(20),
-                ])
+                .constraints(column_constraints(app.show_details))
                 .split(layout[2])[1];
 
-            let border_color = match app.display_commands.len() {
-                0 => Color::Red,
+            let border_color = match (app.display_commands.len(), &app.query_error) {
+                (_, Some(_)) => Color::Red,
+                (0, _) if !app.affected_pending() => Color::Red,
                 _ => Color::Gray,
             };
 
-            // Style the textarea directly
-            textarea.set_block(
-                Block::default()
-                    .borders(Borders::ALL)
-                    .border_style(Style::default().fg(border_color)),
-            );
+            // Style the textarea directly, with any query error along the bottom edge
+            let mut search_block = Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(border_color));
+            if let Some(error) = &app.query_error {
+                search_block = search_block.title_bottom(Span::styled(
+                    format!(" {} ", error),
+                    Style::default().fg(Color::Red),
+                ));
+            }
+            textarea.set_block(search_block);
 
             // Render it directly - no .widget() needed
             frame.render_widget(&textarea, search_area);
+
+            if let Some(cmd) = &app.offer {
+                render_offer(frame, area, cmd, &app.base_ref);
+            }
+            if let Some((_, prompt)) = &mut app.args_prompt {
+                prompt.render(frame, area);
+            }
+            if let Some((_, form)) = &mut app.option_form {
+                form.render(frame, area);
+            }
+            if let Some(runner) = &mut app.runner {
+                runner.render(frame, area);
+            }
         })?;
 
         // Handle input
         if event::poll(std::time::Duration::from_millis(10))? {
-            match event::read()?.into() {
+            let input: Input = event::read()?.into();
+            app.notice = None;
+
+            // The output pane takes every key until it's closed
+            if let Some(runner) = &mut app.runner {
+                if let RunnerAction::Close = runner.input(input) {
+                    app.runner = None;
+                }
+                continue;
+            }
+
+            // The option form takes every key until it's run or cancelled
+            if let Some((cmd, mut form)) = app.option_form.take() {
+                match form.input(input) {
+                    FormAction::Editing => app.option_form = Some((cmd, form)),
+                    FormAction::Cancel => (),
+                    FormAction::Run(overrides) => app.launch_with_overrides(&cmd, &overrides),
+                }
+                continue;
+            }
+
+            // The argument prompt takes every key until it's run or cancelled
+            if let Some((cmd, mut prompt)) = app.args_prompt.take() {
+                match prompt.input(input) {
+                    PromptAction::Editing => app.args_prompt = Some((cmd, prompt)),
+                    PromptAction::Cancel => (),
+                    PromptAction::Run(words, args) => app.launch_with_args(&cmd, &words, &args),
+                }
+                continue;
+            }
+
+            // The run/affected choice takes every key until it's answered
+            if let Some(cmd) = app.offer.take() {
+                match input {
+                    Input {
+                        key: Key::Char('a'),
+                        ..
+                    } => app.launch_affected(&cmd),
+                    Input {
+                        key: Key::Enter, ..
+                    }
+                    | Input {
+                        key: Key::Char('r'),
+                        ..
+                    } => app.launch_one(&cmd),
+                    Input { key: Key::Esc, .. } => (),
+                    _ => app.offer = Some(cmd),
+                }
+                continue;
+            }
+
+            match input {
                 Input { key: Key::Esc, .. } => break,
                 Input {
                     key: Key::Char('r'),
                     ctrl: true,
                     ..
                 } => nx_reset(),
+                Input {
+                    key: Key::Char('d'),
+                    ctrl: true,
+                    ..
+                } => app.toggle_diagnostics(),
+                Input {
+                    key: Key::Char('t'),
+                    ctrl: true,
+                    ..
+                } => app.toggle_details(),
+                Input {
+                    key: Key::Char('p'),
+                    ctrl: true,
+                    ..
+                } => app.toggle_favourite(),
+                Input {
+                    key: Key::Char('a'),
+                    ctrl: true,
+                    ..
+                } => app.toggle_affected(),
+                Input {
+                    key: Key::Char('g'),
+                    ctrl: true,
+                    ..
+                } if app.show_affected => app.refresh_affected(),
+                // Only terminals that report modifiers on enter send shift + enter
+                Input {
+                    key: Key::Char('e'),
+                    ctrl: true,
+                    ..
+                }
+                | Input {
+                    key: Key::Enter,
+                    shift: true,
+                    ..
+                } => app.prompt_args(),
+                Input {
+                    key: Key::Char('o'),
+                    ctrl: true,
+                    ..
+                } => app.open_option_form(),
                 Input {
                     key: Key::Char('c'),
                     ctrl: true,
                     ..
                 } => break,
-                Input { key: Key::Down, .. } => app.next(),
-                Input { key: Key::Up, .. } => app.previous(),
-                Input { key: Key::Tab, .. } => {
-                    if app.select() {
-                        break;
-                    }
+                Input { key: Key::Down, .. } => {
+                    app.next();
+                    app.list_focused = true;
+                }
+                Input { key: Key::Up, .. } => {
+                    app.previous();
+                    app.list_focused = true;
                 }
                 Input {
+                    key: Key::Char(' '),
+                    ctrl: false,
+                    alt: false,
+                    ..
+                } if app.space_marks() => app.toggle_mark(),
+                Input { key: Key::Tab, .. }
+                | Input {
                     key: Key::Enter, ..
-                } => {
-                    if app.select() {
-                        break;
-                    }
-                }
+                } => app.select(),
                 input => {
-                    textarea.input(input);
+                    if textarea.input(input) {
+                        app.list_focused = false;
+                    }
                     let search_text = textarea.lines()[0].to_string();
                     app.filter_commands(&search_text);
                 }
@@ -398,52 +1317,319 @@ pub fn run_app(search_path: String) -> Result<()> {
     Ok(())
 }
 
+// Splits `text` into spans with the characters the search matched picked out, padded out to
+// `width`. `start` is where `text` begins in the display string the indices refer to.
+fn highlighted(
+    text: &str,
+    width: usize,
+    start: usize,
+    matched_indices: &[usize],
+    style: Style,
+) -> Vec<Span<'static>> {
+    // Underlined as well as coloured so it still shows on the selected row
+    let matched_style = style
+        .fg(Color::LightCyan)
+        .add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+
+    let mut spans: Vec<Span<'static>> = Vec::new();
+    let mut run = String::new();
+    let mut run_matched = false;
+    for (i, c) in text.chars().enumerate() {
+        let is_matched = matched_indices.binary_search(&(start + i)).is_ok();
+        if is_matched != run_matched && !run.is_empty() {
+            let run_style = if run_matched { matched_style } else { style };
+            spans.push(Span::styled(std::mem::take(&mut run), run_style));
+        }
+        run_matched = is_matched;
+        run.push(c);
+    }
+    if !run.is_empty() {
+        let run_style = if run_matched { matched_style } else { style };
+        spans.push(Span::styled(run, run_style));
+    }
+
+    let used = text.chars().count();
+    spans.push(Span::raw(" ".repeat(width.saturating_sub(used))));
+    spans
+}
+
+// Asks whether to run just the picked command or its target for every affected project
+fn render_offer(frame: &mut Frame, area: Rect, cmd: &CommandEntry, base_ref: &str) {
+    let width = 64.min(area.width);
+    let height = 5.min(area.height);
+    let popup = Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    };
+
+    let mut affected = format!("nx affected -t {} --base={}", cmd.command, base_ref);
+    if let Some(configuration) = &cmd.subcommand {
+        affected.push_str(&format!(" --configuration={}", configuration));
+    }
+
+    let key_style = Style::default().fg(Color::Yellow);
+    let lines = vec![
+        Line::from(vec![
+            Span::styled("enter  ", key_style),
+            Span::raw(format!("nx run {}", cmd.to_nx_command())),
+        ]),
+        Line::from(vec![
+            Span::styled("a      ", key_style),
+            Span::raw(affected),
+        ]),
+        Line::from(vec![
+            Span::styled("esc    ", key_style),
+            Span::raw("cancel"),
+        ]),
+    ];
+
+    frame.render_widget(Clear, popup);
+    frame.render_widget(
+        Paragraph::new(lines).block(Block::bordered().title("Run")),
+        popup,
+    );
+}
+
+fn command_list<'a>(items: Vec<ListItem<'a>>, title: &'a str) -> List<'a> {
+    // Making the selection highlight more visible with a darker background
+    List::new(items)
+        .block(Block::bordered().title(title))
+        .highlight_style(
+            Style::default()
+                .bg(Color::DarkGray)
+                .fg(Color::White) // Makes the text really pop when selected
+                .add_modifier(Modifier::BOLD),
+        )
+}
+
+fn column_constraints(show_details: bool) -> [Constraint; 3] {
+    if show_details {
+        [
+            Constraint::Percentage(5),
+            Constraint::Percentage(60),
+            Constraint::Percentage(35),
+        ]
+    } else {
+        [
+            Constraint::Percentage(20),
+            Constraint::Percentage(60),
+            Constraint::Percentage(20),
+        ]
+    }
+}
+
+// One badge per framework, the primary one stands out, padded out to a fixed column width
+fn framework_badges(framework_names: &[String], width: usize) -> Vec<Span<'static>> {
+    let mut spans = Vec::new();
+    let mut used = 0;
+
+    for (i, name) in framework_names.iter().enumerate() {
+        let style = if i == 0 {
+            Style::default()
+                .fg(Color::LightBlue)
+                .add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::Blue)
+        };
+        spans.push(Span::styled(format!("{} ", name), style));
+        used += name.len() + 1;
+    }
+
+    spans.push(Span::raw(" ".repeat(width.saturating_sub(used))));
+    spans
+}
+
 fn construct(projects: &Vec<Project>) -> Vec<CommandEntry> {
     let mut cmds: Vec<CommandEntry> = Vec::new();
 
     for project in projects {
-        let framework_name = project.framework.map(|f| f.name.to_string());
+        let framework_names: Vec<String> = project
+            .frameworks
+            .iter()
+            .map(|f| match &f.version {
+                Some(version) => format!("{} {}", f.framework.name, version),
+                None => f.framework.name.clone(),
+            })
+            .collect();
 
         // Add regular tasks
         for task in &project.tasks {
+            let task = Rc::new(task.clone());
+
             // Add main command
             cmds.push(CommandEntry {
                 project_type: project.project_type.clone(),
-                framework_name: framework_name.clone(),
+                framework_names: framework_names.clone(),
+                tags: project.tags.clone(),
                 project_name: project.name.clone(),
                 command: task.command.clone(),
                 subcommand: None,
+                task: Some(task.clone()),
+                matched_indices: vec![],
             });
 
             // Add subcommands
             for subcmd in &task.subcommands {
                 cmds.push(CommandEntry {
                     project_type: project.project_type.clone(),
-                    framework_name: framework_name.clone(),
+                    framework_names: framework_names.clone(),
+                    tags: project.tags.clone(),
                     project_name: project.name.clone(),
                     command: task.command.clone(),
                     subcommand: Some(subcmd.clone()),
+                    task: Some(task.clone()),
+                    matched_indices: vec![],
                 });
             }
         }
 
-        // Add framework commands
-        if let Some(framework) = &project.framework {
-            for cmd in framework.commands {
+        // Add framework commands, from every framework the project uses
+        for detected in &project.frameworks {
+            for cmd in &detected.framework.commands {
                 cmds.push(CommandEntry {
                     project_type: project.project_type.clone(),
-                    framework_name: Some(framework.name.to_string()),
+                    framework_names: framework_names.clone(),
+                    tags: project.tags.clone(),
                     project_name: project.name.clone(),
                     command: cmd.to_string(),
                     subcommand: None,
+                    task: None,
+                    matched_indices: vec![],
                 });
             }
         }
     }
 
     // Remove duplicates based on the actual command that would be run
-    cmds.sort_by(|a, b| a.to_nx_command().cmp(&b.to_nx_command()));
+    cmds.sort_by_key(|c| c.to_nx_command());
     cmds.dedup_by(|a, b| a.to_nx_command() == b.to_nx_command());
 
     cmds
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // `project:target` or `project:target:configuration`
+    fn entry(nx_command: &str) -> CommandEntry {
+        let mut parts = nx_command.split(':');
+        CommandEntry {
+            project_type: ProjectType::Application,
+            framework_names: vec![],
+            project_name: parts.next().unwrap().to_string(),
+            tags: vec![],
+            command: parts.next().unwrap().to_string(),
+            subcommand: parts.next().map(|s| s.to_string()),
+            task: None,
+            matched_indices: vec![],
+        }
+    }
+
+    fn group(marked: &[&str]) -> Vec<(Vec<String>, Vec<String>, Option<String>)> {
+        let marked: Vec<CommandEntry> = marked.iter().map(|m| entry(m)).collect();
+        RunMany::group(&marked)
+            .into_iter()
+            .map(|run| (run.projects, run.targets, run.configuration))
+            .collect()
+    }
+
+    fn strings(words: &[&str]) -> Vec<String> {
+        words.iter().map(|w| w.to_string()).collect()
+    }
+
+    fn matched(cmd: &CommandEntry, search: &str) -> Vec<usize> {
+        let matcher = SkimMatcherV2::default();
+        App::fuzzy_match(&matcher, cmd, &App::prep_for_matching(search))
+            .expect("the search should match")
+            .1
+    }
+
+    fn nextjs_app() -> CommandEntry {
+        CommandEntry {
+            framework_names: vec!["nextjs 14.1".to_string()],
+            ..entry("web-app:build")
+        }
+    }
+
+    #[test]
+    fn matches_in_the_name_skip_stripped_characters() {
+        let cmd = nextjs_app();
+        assert_eq!(cmd.display_string(), "[app:nextjs 14.1] web-app:build");
+        assert_eq!(cmd.name_start(), 18);
+        assert_eq!(matched(&cmd, "web-app"), vec![18, 19, 20, 22, 23, 24]);
+    }
+
+    #[test]
+    fn matches_in_the_command() {
+        let cmd = nextjs_app();
+        assert_eq!(cmd.command_start(), 26);
+        assert_eq!(matched(&cmd, "build"), vec![26, 27, 28, 29, 30]);
+    }
+
+    #[test]
+    fn matches_in_the_reversed_words_map_back_to_where_they_are_shown() {
+        // "build" comes after "next" in the row, only the reversed copy has them in this order
+        assert_eq!(
+            matched(&nextjs_app(), "build next"),
+            vec![5, 6, 7, 8, 26, 27, 28, 29, 30]
+        );
+    }
+
+    #[test]
+    fn chars_that_lowercase_to_several_map_back_to_one() {
+        // İ lowercases to i and a combining dot
+        let cmd = entry("İcons:build");
+        assert_eq!(cmd.name_start(), 6);
+        assert_eq!(matched(&cmd, "icons"), vec![6, 7, 8, 9, 10]);
+    }
+
+    #[test]
+    fn targets_on_the_same_projects_share_a_run_many() {
+        assert_eq!(
+            group(&["a:build", "b:build", "b:test", "a:test"]),
+            vec![(strings(&["a", "b"]), strings(&["build", "test"]), None)]
+        );
+    }
+
+    #[test]
+    fn targets_on_different_projects_run_separately() {
+        assert_eq!(
+            group(&["a:build", "b:build", "a:test"]),
+            vec![
+                (strings(&["a", "b"]), strings(&["build"]), None),
+                (strings(&["a"]), strings(&["test"]), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn configurations_are_never_folded_together() {
+        assert_eq!(
+            group(&["a:build:production", "a:test", "a:lint:production"]),
+            vec![
+                (
+                    strings(&["a"]),
+                    strings(&["build", "lint"]),
+                    Some("production".to_string())
+                ),
+                (strings(&["a"]), strings(&["test"]), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn every_marked_command_is_covered() {
+        let marked: Vec<CommandEntry> = ["a:build", "b:build", "a:test", "b:test"]
+            .iter()
+            .map(|m| entry(m))
+            .collect();
+        let covers: Vec<String> = RunMany::group(&marked)
+            .into_iter()
+            .flat_map(|run| run.covers)
+            .collect();
+        assert_eq!(covers, strings(&["a:build", "b:build", "a:test", "b:test"]));
+    }
+}
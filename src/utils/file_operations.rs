@@ -1,38 +1,111 @@
+use ignore::{overrides::OverrideBuilder, WalkBuilder, WalkState};
 use std::fs;
 use std::path::Path;
+use std::sync::Mutex;
 
-pub fn find_files(dir: &Path, target_files: &[&str]) -> Vec<String> {
-    let mut results = Vec::new();
+// Directories that never contain real projects, whatever the ignore files say
+const ALWAYS_IGNORED: &[&str] = &["node_modules", ".git", ".nx"];
 
-    if let Ok(entries) = fs::read_dir(dir) {
-        for entry in entries.flatten() {
+// Walks `dir` for the target filenames, skipping anything excluded by .gitignore, .nxignore
+// or one of the extra `ignore_globs` (gitignore syntax, relative to `dir`)
+pub fn find_files(dir: &Path, target_files: &[&str], ignore_globs: &[String]) -> Vec<String> {
+    let results = Mutex::new(Vec::new());
+
+    let mut overrides = OverrideBuilder::new(dir);
+    for glob in ALWAYS_IGNORED
+        .iter()
+        .map(|g| g.to_string())
+        .chain(ignore_globs.iter().cloned())
+    {
+        // Overrides whitelist by default, a leading ! turns the glob into an ignore
+        let _ = overrides.add(&format!("!{}", glob));
+    }
+
+    let mut walker = WalkBuilder::new(dir);
+    walker
+        .hidden(false)
+        .ignore(false)
+        .git_global(false)
+        .require_git(false)
+        .add_custom_ignore_filename(".nxignore");
+    if let Ok(overrides) = overrides.build() {
+        walker.overrides(overrides);
+    }
+
+    walker.build_parallel().run(|| {
+        Box::new(|entry| {
+            let Ok(entry) = entry else {
+                return WalkState::Continue;
+            };
             let path = entry.path();
 
             // Check if it's a file and matches any of our target filenames
-            if path.is_file() {
-                if let Some(filename) = path.file_name() {
-                    if let Some(filename_str) = filename.to_str() {
-                        if target_files.contains(&filename_str) {
-                            if let Some(path_str) = path.to_str() {
-                                results.push(path_str.to_string());
-                            }
+            if entry.file_type().is_some_and(|t| t.is_file()) {
+                if let Some(filename_str) = path.file_name().and_then(|f| f.to_str()) {
+                    if target_files.contains(&filename_str) {
+                        if let Some(path_str) = path.to_str() {
+                            results.lock().unwrap().push(path_str.to_string());
                         }
                     }
                 }
             }
-            // Recursively search directories
-            else if path.is_dir() {
-                if path.file_name() == Some("node_modules".as_ref()) {
-                    continue;
-                }
-                results.extend(find_files(&path, target_files));
-            }
-        }
-    }
+            WalkState::Continue
+        })
+    });
 
+    // The parallel walk finishes in whatever order, keep the output stable between runs
+    let mut results = results.into_inner().unwrap();
+    results.sort();
     results
 }
 
 pub fn path_exists(file: &Path) -> bool {
-    fs::metadata(&file).is_ok()
+    fs::metadata(file).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::detection::nx_json::NxJson;
+
+    #[test]
+    fn ignored_build_output_is_not_scanned() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        for (path, content) in [
+            ("nx.json", r#"{"cacheDirectory": "./tmp/nx-cache/"}"#),
+            (".gitignore", "/dist\n"),
+            (".nxignore", "out/\n"),
+            ("apps/web/project.json", "{}"),
+            ("apps/web/package.json", "{}"),
+            ("dist/app/project.json", "{}"),
+            ("out/app/project.json", "{}"),
+            ("build/app/project.json", "{}"),
+            ("tmp/nx-cache/app/project.json", "{}"),
+            ("node_modules/lib/package.json", "{}"),
+        ] {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+
+        // --ignore globs plus what nx.json adds
+        let mut ignore_globs = vec!["build".to_string()];
+        ignore_globs.extend(NxJson::read(root).unwrap().ignore_globs());
+
+        let found: Vec<String> = find_files(root, &["project.json", "package.json"], &ignore_globs)
+            .into_iter()
+            .map(|path| {
+                Path::new(&path)
+                    .strip_prefix(root)
+                    .unwrap()
+                    .to_string_lossy()
+                    .replace('\\', "/")
+            })
+            .collect();
+        assert_eq!(
+            found,
+            vec!["apps/web/package.json", "apps/web/project.json"]
+        );
+    }
 }
@@ -1,4 +1,10 @@
 mod file_operations;
+mod storage;
 
 pub use file_operations::find_files;
 pub use file_operations::path_exists;
+#[cfg(test)]
+pub use storage::test_env;
+pub use storage::user_config_dir;
+pub use storage::workspace_cache_dir;
+pub use storage::workspace_state_dir;
@@ -0,0 +1,142 @@
+use std::{
+    env,
+    path::{Path, PathBuf},
+};
+
+// Per-workspace directory under the XDG cache home, e.g. ~/.cache/nxplorer/repo-3f9c2a1b0d4e5f67
+pub fn workspace_cache_dir(base_repo_path: &Path) -> Option<PathBuf> {
+    let cache_home = xdg_dir("XDG_CACHE_HOME", ".cache")?;
+    Some(
+        cache_home
+            .join("nxplorer")
+            .join(workspace_key(base_repo_path)),
+    )
+}
+
+// Per-workspace directory under the XDG state home, for things worth keeping that can't be
+// rebuilt like the cache can, e.g. ~/.local/state/nxplorer/repo-3f9c2a1b0d4e5f67
+pub fn workspace_state_dir(base_repo_path: &Path) -> Option<PathBuf> {
+    let state_home = xdg_dir("XDG_STATE_HOME", ".local/state")?;
+    Some(
+        state_home
+            .join("nxplorer")
+            .join(workspace_key(base_repo_path)),
+    )
+}
+
+// Where the user's own settings live, e.g. ~/.config/nxplorer
+pub fn user_config_dir() -> Option<PathBuf> {
+    Some(xdg_dir("XDG_CONFIG_HOME", ".config")?.join("nxplorer"))
+}
+
+// The XDG base directory in `var`, or its usual spot under the home directory
+fn xdg_dir(var: &str, home_fallback: &str) -> Option<PathBuf> {
+    env::var_os(var)
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(home_fallback)))
+}
+
+// The workspace directory's name for telling them apart by eye, plus a hash of its canonical path
+// so two workspaces never share a directory
+fn workspace_key(base_repo_path: &Path) -> String {
+    let resolved = base_repo_path
+        .canonicalize()
+        .unwrap_or_else(|_| base_repo_path.to_path_buf());
+
+    let name: String = resolved
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+
+    format!(
+        "{}-{:016x}",
+        name,
+        fnv1a(resolved.as_os_str().as_encoded_bytes())
+    )
+}
+
+// FNV-1a, spelled out because std's hasher is allowed to change between Rust releases and the
+// key has to stay the same for the directories to be found again
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+// The environment is process-wide, so tests that read or change the variables above (or PATH)
+// take turns through this, and whatever they change is put back when they're done
+#[cfg(test)]
+pub mod test_env {
+    use std::{
+        env,
+        ffi::{OsStr, OsString},
+        sync::{Mutex, MutexGuard},
+    };
+
+    static LOCK: Mutex<()> = Mutex::new(());
+
+    pub struct EnvGuard {
+        _lock: MutexGuard<'static, ()>,
+        saved: Vec<(&'static str, Option<OsString>)>,
+    }
+
+    pub fn lock() -> EnvGuard {
+        EnvGuard {
+            _lock: LOCK.lock().unwrap_or_else(|e| e.into_inner()),
+            saved: vec![],
+        }
+    }
+
+    impl EnvGuard {
+        pub fn set(&mut self, var: &'static str, value: impl AsRef<OsStr>) {
+            if !self.saved.iter().any(|(saved, _)| *saved == var) {
+                self.saved.push((var, env::var_os(var)));
+            }
+            env::set_var(var, value);
+        }
+    }
+
+    impl Drop for EnvGuard {
+        fn drop(&mut self) {
+            for (var, value) in self.saved.drain(..).rev() {
+                match value {
+                    Some(value) => env::set_var(var, value),
+                    None => env::remove_var(var),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paths_that_flatten_alike_get_different_keys() {
+        let a = workspace_key(Path::new("/nonexistent/a_b"));
+        let b = workspace_key(Path::new("/nonexistent/a/b"));
+        assert_ne!(a, b);
+        assert!(a.starts_with("a_b-"));
+        assert!(b.starts_with("b-"));
+    }
+
+    #[test]
+    fn the_key_is_stable() {
+        assert_eq!(
+            workspace_key(Path::new("/nonexistent/repo")),
+            workspace_key(Path::new("/nonexistent/repo"))
+        );
+        assert_eq!(fnv1a(b"a"), 0xaf63dc4c8601ec8c);
+    }
+}